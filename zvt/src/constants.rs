@@ -242,3 +242,210 @@ pub enum TerminalStatusCode {
     #[error("OPT-data not available (= OPT-Personalisation required)")]
     OptDataNotAvailable = 0xf6,
 }
+
+/// Intermediate status codes as sent in the
+/// [crate::packets::IntermediateStatusInformation].
+///
+/// The [std::fmt::Display] implementation prints the English text, the
+/// alternate form (`{:#}`) the German text shown on the PT.
+#[derive(Debug, PartialEq, FromPrimitive, Clone, Copy)]
+#[repr(u8)]
+pub enum IntermediateStatus {
+    WaitingForAmountConfirmation = 0x00,
+    WatchPinPad = 0x01,
+    WatchPinPadAlternative = 0x02,
+    NotAccepted = 0x03,
+    WaitingForHost = 0x04,
+    SendingAutoReversal = 0x05,
+    SendingPostBookings = 0x06,
+    CardNotAdmitted = 0x07,
+    CardUnknown = 0x08,
+    ExpiredCard = 0x09,
+    InsertCard = 0x0a,
+    RemoveCard = 0x0b,
+    CardNotReadable = 0x0c,
+    ProcessingError = 0x0d,
+    Processing = 0x0e,
+    AutomaticEndOfDay = 0x0f,
+    InvalidCard = 0x10,
+    BalanceDisplay = 0x11,
+    SystemMalfunction = 0x12,
+    PaymentNotPossible = 0x13,
+    CreditNotSufficient = 0x14,
+    IncorrectPin = 0x15,
+    LimitNotSufficient = 0x16,
+    PleaseWait = 0x17,
+    PinTryLimitExceeded = 0x18,
+    CardDataIncorrect = 0x19,
+    ServiceMode = 0x1a,
+    ApprovedPleaseFillUp = 0x1b,
+    ApprovedPleaseTakeGoods = 0x1c,
+    Declined = 0x1d,
+    WaitingForMobileNumber = 0x26,
+    WaitingForMobileNumberRepeat = 0x27,
+    CurrencySelection = 0x28,
+    LanguageSelection = 0x29,
+    InsertCardForLoading = 0x2a,
+    EmergencyTransaction = 0x2b,
+    ApplicationSelection = 0x2c,
+    WaitingForMileage = 0xc7,
+    WaitingForCashier = 0xc8,
+    AutomaticDiagnosis = 0xc9,
+    AutomaticInitialisation = 0xca,
+    MerchantJournalFull = 0xcb,
+    DebitAdviceNotPossible = 0xcc,
+    ConnectingDialUp = 0xd2,
+    DialUpConnectionMade = 0xd3,
+    WaitingForApplicationSelection = 0xe0,
+    WaitingForLanguageSelection = 0xe1,
+    UseCleaningCard = 0xe2,
+    Offline = 0xf1,
+    Online = 0xf2,
+    OfflineTransaction = 0xf3,
+    NoAppropriateStatusCode = 0xff,
+}
+
+impl IntermediateStatus {
+    /// Returns the English text of the status.
+    pub fn english(&self) -> &'static str {
+        self.texts().0
+    }
+
+    /// Returns the German text of the status.
+    pub fn german(&self) -> &'static str {
+        self.texts().1
+    }
+
+    fn texts(&self) -> (&'static str, &'static str) {
+        match self {
+            Self::WaitingForAmountConfirmation => (
+                "PT is waiting for amount-confirmation",
+                "Bitte Betrag bestätigen",
+            ),
+            Self::WatchPinPad => (
+                "Please watch PIN-Pad",
+                "Bitte Anzeigen auf dem PIN-Pad beachten",
+            ),
+            Self::WatchPinPadAlternative => (
+                "Please watch PIN-Pad",
+                "Bitte Anzeigen auf dem PIN-Pad beachten",
+            ),
+            Self::NotAccepted => ("Not accepted", "Vorgang nicht möglich"),
+            Self::WaitingForHost => (
+                "PT is waiting for response from FEP",
+                "Warte auf Antwort vom Rechner",
+            ),
+            Self::SendingAutoReversal => ("PT is sending auto-reversal", "Sende Autostorno"),
+            Self::SendingPostBookings => ("PT is sending post-bookings", "Sende Nachbuchungen"),
+            Self::CardNotAdmitted => ("Card not admitted", "Karte nicht zugelassen"),
+            Self::CardUnknown => ("Card unknown / undefined", "Karte unbekannt / undefiniert"),
+            Self::ExpiredCard => ("Expired card", "Karte verfallen"),
+            Self::InsertCard => ("Insert card", "Karte einstecken"),
+            Self::RemoveCard => ("Please remove card!", "Bitte Karte entnehmen!"),
+            Self::CardNotReadable => ("Card not readable", "Karte nicht lesbar"),
+            Self::ProcessingError => ("Processing error", "Vorgang abgebrochen"),
+            Self::Processing => ("Please wait...", "Vorgang wird bearbeitet, bitte warten..."),
+            Self::AutomaticEndOfDay => (
+                "PT is commencing an automatic end-of-day batch",
+                "Automatischer Tagesabschluss wird eingeleitet",
+            ),
+            Self::InvalidCard => ("Invalid card", "Karte ungültig"),
+            Self::BalanceDisplay => ("Balance display", "Guthabenanzeige"),
+            Self::SystemMalfunction => ("System malfunction", "Systemfehler"),
+            Self::PaymentNotPossible => ("Payment not possible", "Zahlung nicht möglich"),
+            Self::CreditNotSufficient => ("Credit not sufficient", "Guthaben nicht ausreichend"),
+            Self::IncorrectPin => ("Incorrect PIN", "Geheimzahl falsch"),
+            Self::LimitNotSufficient => ("Limit not sufficient", "Limit nicht ausreichend"),
+            Self::PleaseWait => ("Please wait...", "Bitte warten..."),
+            Self::PinTryLimitExceeded => ("PIN try limit exceeded", "Geheimzahl zu oft falsch"),
+            Self::CardDataIncorrect => ("Card-data incorrect", "Kartendaten falsch"),
+            Self::ServiceMode => ("Service-mode", "Servicemodus"),
+            Self::ApprovedPleaseFillUp => (
+                "Approved. Please fill-up",
+                "Autorisierung erfolgt. Bitte tanken",
+            ),
+            Self::ApprovedPleaseTakeGoods => (
+                "Approved. Please take goods",
+                "Zahlung erfolgt. Bitte Ware entnehmen",
+            ),
+            Self::Declined => ("Declined", "Autorisierung nicht möglich"),
+            Self::WaitingForMobileNumber => (
+                "PT is waiting for input of the mobile-number",
+                "Bitte Handynummer eingeben",
+            ),
+            Self::WaitingForMobileNumberRepeat => (
+                "PT is waiting for repeat of mobile number",
+                "Bitte Handynummer wiederholen",
+            ),
+            Self::CurrencySelection => (
+                "Currency selection, please wait...",
+                "Währungsauswahl, bitte warten...",
+            ),
+            Self::LanguageSelection => (
+                "Language selection, please wait...",
+                "Sprachauswahl, bitte warten...",
+            ),
+            Self::InsertCardForLoading => (
+                "For loading please insert card",
+                "Zum Laden Karte einstecken",
+            ),
+            Self::EmergencyTransaction => (
+                "Emergency transaction, please wait",
+                "Offline-Notbetrieb, bitte warten",
+            ),
+            Self::ApplicationSelection => (
+                "Application selection, please wait",
+                "Auswahl Debit/Kredit, bitte warten",
+            ),
+            Self::WaitingForMileage => (
+                "PT is waiting for input of the mileage",
+                "Kilometerstand eingeben",
+            ),
+            Self::WaitingForCashier => ("PT is waiting for cashier", "Warte auf Kassierer"),
+            Self::AutomaticDiagnosis => (
+                "PT is commencing an automatic diagnosis",
+                "Automatische Diagnose wird eingeleitet",
+            ),
+            Self::AutomaticInitialisation => (
+                "PT is commencing an automatic initialisation",
+                "Automatische Initialisierung wird eingeleitet",
+            ),
+            Self::MerchantJournalFull => ("Merchant-journal full", "Händlerjournal voll"),
+            Self::DebitAdviceNotPossible => (
+                "Debit advice not possible, PIN required",
+                "Lastschrift nicht möglich, PIN notwendig",
+            ),
+            Self::ConnectingDialUp => ("Connecting dial-up", "DFÜ-Verbindung wird hergestellt"),
+            Self::DialUpConnectionMade => ("Dial-up connection made", "DFÜ-Verbindung besteht"),
+            Self::WaitingForApplicationSelection => (
+                "PT is waiting for application-selection",
+                "Bitte Anwendung auswählen",
+            ),
+            Self::WaitingForLanguageSelection => (
+                "PT is waiting for language-selection",
+                "Bitte Sprache auswählen",
+            ),
+            Self::UseCleaningCard => (
+                "PT requests to use the cleaning card",
+                "Bitte Reinigungskarte verwenden",
+            ),
+            Self::Offline => ("Offline", "Offline"),
+            Self::Online => ("Online", "Online"),
+            Self::OfflineTransaction => ("Offline transaction", "Offline-Transaktion"),
+            Self::NoAppropriateStatusCode => (
+                "No appropriate ZVT status code matches, see the TLV container",
+                "Kein passender ZVT-Statuscode, siehe TLV-Container",
+            ),
+        }
+    }
+}
+
+impl std::fmt::Display for IntermediateStatus {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        if f.alternate() {
+            f.write_str(self.german())
+        } else {
+            f.write_str(self.english())
+        }
+    }
+}
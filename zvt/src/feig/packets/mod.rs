@@ -93,6 +93,56 @@ pub struct WriteFileParameter {
     pub adpu_size: u32,
 }
 
+/// Read File.
+///
+/// Requests one chunk of the file given by its id and offset. The PT replies
+/// with a [ReadFileCompletion] containing the chunk.
+#[derive(Debug, PartialEq, Zvt)]
+#[zvt_control_field(class = 0x08, instr = 0x11)]
+pub struct ReadFile {
+    #[zvt_bmp(length = length::Fixed<3>, encoding = encoding::Bcd)]
+    pub password: usize,
+
+    #[zvt_bmp(number = 0x06, length = length::Tlv)]
+    pub tlv: Option<tlv::WriteData>,
+}
+
+/// The chunk of the file requested with [ReadFile].
+///
+/// The PT answers with the regular Completion (06 0F, see
+/// [crate::packets::CompletionData]) but carries the chunk in BMP 06, using
+/// the file tags of the Write File from cVEND 6.13, Table 2.
+#[derive(Debug, PartialEq, Zvt)]
+#[zvt_control_field(class = 0x06, instr = 0x0f)]
+pub struct ReadFileCompletion {
+    #[zvt_bmp(number = 0x06, length = length::Tlv)]
+    pub tlv: Option<tlv::WriteData>,
+}
+
+/// The parameters to read a file. Like the [WriteFileParameter] this packet is
+/// never sent - we use it in the sequences::ReadFile.
+#[derive(Debug, PartialEq, Zvt)]
+#[zvt_control_field(class = 0x08, instr = 0x11)]
+pub struct ReadFileParameter {
+    pub path: String,
+    pub password: usize,
+    pub file_id: u8,
+}
+
+/// Delete File.
+///
+/// Deletes the files given by their ids from the PT, e.x. the payload of an
+/// interrupted update.
+#[derive(Debug, PartialEq, Zvt)]
+#[zvt_control_field(class = 0x08, instr = 0x12)]
+pub struct DeleteFile {
+    #[zvt_bmp(length = length::Fixed<3>, encoding = encoding::Bcd)]
+    pub password: usize,
+
+    #[zvt_bmp(number = 0x06, length = length::Tlv)]
+    pub tlv: Option<tlv::WriteFile>,
+}
+
 /// Configuration packages. They all use the "Change Configuration" flow, but
 /// with vastly different parameters, hence we have one for each flow. The Change Configuration
 /// is described in 2.40, but since this is very hardware manufacturer specific, we put this one
@@ -104,6 +154,68 @@ pub struct ChangeConfiguration {
     pub tlv: tlv::ChangeConfiguration,
 }
 
+impl ChangeConfiguration {
+    fn with(system_information: tlv::SystemInformation) -> Self {
+        Self {
+            tlv: tlv::ChangeConfiguration { system_information },
+        }
+    }
+
+    /// Changes the host configuration.
+    pub fn host(password: usize, data: tlv::HostConfigurationData) -> Self {
+        Self::with(tlv::SystemInformation {
+            password,
+            host_configuration_data: Some(data),
+            ..tlv::SystemInformation::default()
+        })
+    }
+
+    /// Changes the display and brightness.
+    pub fn display(password: usize, data: tlv::DisplayConfigurationData) -> Self {
+        Self::with(tlv::SystemInformation {
+            password,
+            display_configuration_data: Some(data),
+            ..tlv::SystemInformation::default()
+        })
+    }
+
+    /// Changes the buzzer volume.
+    pub fn volume(password: usize, data: tlv::VolumeConfigurationData) -> Self {
+        Self::with(tlv::SystemInformation {
+            password,
+            volume_configuration_data: Some(data),
+            ..tlv::SystemInformation::default()
+        })
+    }
+
+    /// Changes the default language.
+    pub fn language(password: usize, data: tlv::LanguageConfigurationData) -> Self {
+        Self::with(tlv::SystemInformation {
+            password,
+            language_configuration_data: Some(data),
+            ..tlv::SystemInformation::default()
+        })
+    }
+
+    /// Changes the contactless reader settings.
+    pub fn contactless(password: usize, data: tlv::ContactlessConfigurationData) -> Self {
+        Self::with(tlv::SystemInformation {
+            password,
+            contactless_configuration_data: Some(data),
+            ..tlv::SystemInformation::default()
+        })
+    }
+
+    /// Changes the network settings.
+    pub fn network(password: usize, data: tlv::NetworkConfigurationData) -> Self {
+        Self::with(tlv::SystemInformation {
+            password,
+            network_configuration_data: Some(data),
+            ..tlv::SystemInformation::default()
+        })
+    }
+}
+
 /// Feig, 5.1
 #[derive(Debug, PartialEq, Zvt)]
 #[zvt_control_field(class = 0x0f, instr = 0xa1)]
@@ -262,6 +374,63 @@ mod test {
         assert_eq!(bytes, expected.zvt_serialize());
     }
 
+    #[rstest::rstest]
+    fn test_read_file() {
+        let bytes = get_bytes("read_file.blob");
+        let expected = ReadFile {
+            password: 123456,
+            tlv: Some(tlv::WriteData {
+                file: Some(tlv::File {
+                    file_id: Some(0x13),
+                    file_offset: Some(1024),
+                    file_size: None,
+                    payload: None,
+                }),
+            }),
+        };
+        assert_eq!(ReadFile::zvt_deserialize(&bytes).unwrap().0, expected);
+        assert_eq!(bytes, expected.zvt_serialize());
+
+        let bytes = get_bytes("read_file_completion.blob");
+        let expected = ReadFileCompletion {
+            tlv: Some(tlv::WriteData {
+                file: Some(tlv::File {
+                    file_id: Some(0x13),
+                    file_offset: Some(1024),
+                    file_size: Some(1028),
+                    payload: Some(b"spec".to_vec()),
+                }),
+            }),
+        };
+        assert_eq!(
+            ReadFileCompletion::zvt_deserialize(&bytes).unwrap().0,
+            expected
+        );
+        assert_eq!(bytes, expected.zvt_serialize());
+    }
+
+    #[rstest::rstest]
+    fn test_delete_file() {
+        let bytes = get_bytes("delete_file.blob");
+        let expected = DeleteFile {
+            password: 123456,
+            tlv: Some(tlv::WriteFile {
+                files: vec![
+                    tlv::File {
+                        file_id: Some(0x20),
+                        ..tlv::File::default()
+                    },
+                    tlv::File {
+                        file_id: Some(0x21),
+                        ..tlv::File::default()
+                    },
+                ],
+            }),
+        };
+        assert_eq!(DeleteFile::zvt_deserialize(&bytes).unwrap().0, expected);
+        assert_eq!(bytes, expected.zvt_serialize());
+    }
+
     #[rstest::rstest]
     fn test_cvend_functions() {
         let bytes = get_bytes("1680761818.690979000_ecr_pt.blob");
@@ -314,6 +483,7 @@ mod test {
                         port: 30401,
                         config_byte: 1,
                     }),
+                    ..tlv::SystemInformation::default()
                 },
             },
         };
@@ -323,4 +493,111 @@ mod test {
         );
         assert_eq!(bytes, expected.zvt_serialize());
     }
+
+    #[rstest::rstest]
+    fn test_change_display_config() {
+        let bytes = [
+            0x08, 0x13, 0x10, 0x06, 0x0e, 0xe4, 0x0c, // Header, BMP 06 and tag e4.
+            0xff, 0x40, 0x03, 0x12, 0x34, 0x56, // The password.
+            0xff, 0x42, 0x03, 0x50, 0x00, 0x3c, // Brightness and backlight timeout.
+        ];
+        let expected = ChangeConfiguration::display(
+            123456,
+            tlv::DisplayConfigurationData {
+                brightness: 80,
+                backlight_timeout: 60,
+            },
+        );
+        assert_eq!(
+            ChangeConfiguration::zvt_deserialize(&bytes).unwrap().0,
+            expected
+        );
+        assert_eq!(bytes.to_vec(), expected.zvt_serialize());
+    }
+
+    #[rstest::rstest]
+    fn test_change_volume_config() {
+        let bytes = [
+            0x08, 0x13, 0x0e, 0x06, 0x0c, 0xe4, 0x0a, // Header, BMP 06 and tag e4.
+            0xff, 0x40, 0x03, 0x12, 0x34, 0x56, // The password.
+            0xff, 0x43, 0x01, 0x46, // The volume.
+        ];
+        let expected =
+            ChangeConfiguration::volume(123456, tlv::VolumeConfigurationData { volume: 70 });
+        assert_eq!(
+            ChangeConfiguration::zvt_deserialize(&bytes).unwrap().0,
+            expected
+        );
+        assert_eq!(bytes.to_vec(), expected.zvt_serialize());
+    }
+
+    #[rstest::rstest]
+    fn test_change_language_config() {
+        let bytes = [
+            0x08, 0x13, 0x0f, 0x06, 0x0d, 0xe4, 0x0b, // Header, BMP 06 and tag e4.
+            0xff, 0x40, 0x03, 0x12, 0x34, 0x56, // The password.
+            0xff, 0x44, 0x02, b'e', b'n', // The language.
+        ];
+        let expected = ChangeConfiguration::language(
+            123456,
+            tlv::LanguageConfigurationData {
+                language: "en".to_string(),
+            },
+        );
+        assert_eq!(
+            ChangeConfiguration::zvt_deserialize(&bytes).unwrap().0,
+            expected
+        );
+        assert_eq!(bytes.to_vec(), expected.zvt_serialize());
+    }
+
+    #[rstest::rstest]
+    fn test_change_contactless_config() {
+        let bytes = [
+            0x08, 0x13, 0x14, 0x06, 0x12, 0xe4, 0x10, // Header, BMP 06 and tag e4.
+            0xff, 0x40, 0x03, 0x12, 0x34, 0x56, // The password.
+            0xff, 0x45, 0x07, 0x01, // Enabled.
+            0x00, 0x00, 0x00, 0x00, 0x50, 0x00, // The CVM limit.
+        ];
+        let expected = ChangeConfiguration::contactless(
+            123456,
+            tlv::ContactlessConfigurationData {
+                enabled: 1,
+                cvm_limit: 5000,
+            },
+        );
+        assert_eq!(
+            ChangeConfiguration::zvt_deserialize(&bytes).unwrap().0,
+            expected
+        );
+        assert_eq!(bytes.to_vec(), expected.zvt_serialize());
+    }
+
+    #[rstest::rstest]
+    fn test_change_network_config() {
+        let bytes = [
+            0x08, 0x13, 0x1e, 0x06, 0x1c, 0xe4, 0x1a, // Header, BMP 06 and tag e4.
+            0xff, 0x40, 0x03, 0x12, 0x34, 0x56, // The password.
+            0xff, 0x46, 0x11, 0x00, // No DHCP.
+            0xc0, 0xa8, 0x00, 0x0a, // The ip.
+            0xff, 0xff, 0xff, 0x00, // The netmask.
+            0xc0, 0xa8, 0x00, 0x01, // The gateway.
+            0x08, 0x08, 0x08, 0x08, // The dns.
+        ];
+        let expected = ChangeConfiguration::network(
+            123456,
+            tlv::NetworkConfigurationData {
+                dhcp: 0,
+                ip: Ipv4Addr::new(192, 168, 0, 10).into(),
+                netmask: Ipv4Addr::new(255, 255, 255, 0).into(),
+                gateway: Ipv4Addr::new(192, 168, 0, 1).into(),
+                dns: Ipv4Addr::new(8, 8, 8, 8).into(),
+            },
+        );
+        assert_eq!(
+            ChangeConfiguration::zvt_deserialize(&bytes).unwrap().0,
+            expected
+        );
+        assert_eq!(bytes.to_vec(), expected.zvt_serialize());
+    }
 }
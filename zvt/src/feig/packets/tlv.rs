@@ -89,6 +89,71 @@ pub struct HostConfigurationData {
     pub config_byte: u8,
 }
 
+/// Configuration of the display and its brightness,
+/// tag 0xff42 of the [SystemInformation].
+#[derive(Debug, PartialEq, Zvt, Default)]
+pub struct DisplayConfigurationData {
+    // The brightness in percent.
+    pub brightness: u8,
+
+    // The time in seconds until the backlight is dimmed.
+    #[zvt_bmp(encoding = encoding::BigEndian)]
+    pub backlight_timeout: u16,
+}
+
+/// Configuration of the buzzer volume, tag 0xff43 of the [SystemInformation].
+#[derive(Debug, PartialEq, Zvt, Default)]
+pub struct VolumeConfigurationData {
+    // The volume of the buzzer in percent.
+    pub volume: u8,
+}
+
+/// Configuration of the default language,
+/// tag 0xff44 of the [SystemInformation].
+#[derive(Debug, PartialEq, Zvt, Default)]
+pub struct LanguageConfigurationData {
+    // The default language as ISO 639-1 code, e.x. `de` or `en`.
+    #[zvt_bmp(length = length::Fixed<2>)]
+    pub language: String,
+}
+
+/// Configuration of the contactless reader,
+/// tag 0xff45 of the [SystemInformation].
+#[derive(Debug, PartialEq, Zvt, Default)]
+pub struct ContactlessConfigurationData {
+    // 1 enables and 0 disables the contactless reader.
+    pub enabled: u8,
+
+    // The amount (in cents) up to which no cardholder verification is
+    // required.
+    #[zvt_bmp(length = length::Fixed<6>, encoding = encoding::Bcd)]
+    pub cvm_limit: usize,
+}
+
+/// Configuration of the network (IP) settings,
+/// tag 0xff46 of the [SystemInformation].
+#[derive(Debug, PartialEq, Zvt, Default)]
+pub struct NetworkConfigurationData {
+    // 1 enables DHCP, the remaining fields are then ignored.
+    pub dhcp: u8,
+
+    #[zvt_bmp(encoding = encoding::BigEndian)]
+    pub ip: u32,
+
+    #[zvt_bmp(encoding = encoding::BigEndian)]
+    pub netmask: u32,
+
+    #[zvt_bmp(encoding = encoding::BigEndian)]
+    pub gateway: u32,
+
+    #[zvt_bmp(encoding = encoding::BigEndian)]
+    pub dns: u32,
+}
+
+/// The configuration groups of the Change Configuration, see cVEND 6.7-6.16.
+///
+/// Only the host configuration (0xff41) is verified against a captured
+/// exchange with a PT; the tags 0xff42-0xff46 are taken from the manual.
 #[derive(Debug, PartialEq, Zvt, Default)]
 pub struct SystemInformation {
     #[zvt_tlv(encoding = encoding::Bcd, tag = 0xff40)]
@@ -96,6 +161,21 @@ pub struct SystemInformation {
 
     #[zvt_tlv(tag = 0xff41)]
     pub host_configuration_data: Option<HostConfigurationData>,
+
+    #[zvt_tlv(tag = 0xff42)]
+    pub display_configuration_data: Option<DisplayConfigurationData>,
+
+    #[zvt_tlv(tag = 0xff43)]
+    pub volume_configuration_data: Option<VolumeConfigurationData>,
+
+    #[zvt_tlv(tag = 0xff44)]
+    pub language_configuration_data: Option<LanguageConfigurationData>,
+
+    #[zvt_tlv(tag = 0xff45)]
+    pub contactless_configuration_data: Option<ContactlessConfigurationData>,
+
+    #[zvt_tlv(tag = 0xff46)]
+    pub network_configuration_data: Option<NetworkConfigurationData>,
 }
 
 #[derive(Debug, PartialEq, Zvt, Default)]
@@ -40,33 +40,36 @@ pub struct File {
     pub path: String,
 }
 
+/// The file ids as in 6.13, Table 2, with the path relative to the payload
+/// directory.
+const FILE_IDS: [(&str, u8); 21] = [
+    ("firmware/kernel.gz", 0x10),
+    ("firmware/rootfs.gz", 0x11),
+    ("firmware/components.tar.gz", 0x12),
+    ("firmware/update.spec", 0x13),
+    ("firmware/update_extended.spec", 0x14),
+    ("app0/update.spec", 0x20),
+    ("app0/update.tar.gz", 0x21),
+    ("app1/update.spec", 0x22),
+    ("app1/update.tar.gz", 0x23),
+    ("app2/update.spec", 0x24),
+    ("app2/update.tar.gz", 0x25),
+    ("app3/update.spec", 0x26),
+    ("app3/update.tar.gz", 0x27),
+    ("app4/update.spec", 0x28),
+    ("app4/update.tar.gz", 0x29),
+    ("app5/update.spec", 0x30),
+    ("app5/update.tar.gz", 0x31),
+    ("app6/update.spec", 0x32),
+    ("app6/update.tar.gz", 0x33),
+    ("app7/update.spec", 0x34),
+    ("app7/update.tar.gz", 0x35),
+];
+
 fn convert_dir(dir: &Path) -> Result<HashMap<u8, String>> {
-    let valid_paths = [
-        (Path::new("firmware/kernel.gz"), 0x10),
-        (Path::new("firmware/rootfs.gz"), 0x11),
-        (Path::new("firmware/components.tar.gz"), 0x12),
-        (Path::new("firmware/update.spec"), 0x13),
-        (Path::new("firmware/update_extended.spec"), 0x14),
-        (Path::new("app0/update.spec"), 0x20),
-        (Path::new("app0/update.tar.gz"), 0x21),
-        (Path::new("app1/update.spec"), 0x22),
-        (Path::new("app1/update.tar.gz"), 0x23),
-        (Path::new("app2/update.spec"), 0x24),
-        (Path::new("app2/update.tar.gz"), 0x25),
-        (Path::new("app3/update.spec"), 0x26),
-        (Path::new("app3/update.tar.gz"), 0x27),
-        (Path::new("app4/update.spec"), 0x28),
-        (Path::new("app4/update.tar.gz"), 0x29),
-        (Path::new("app5/update.spec"), 0x30),
-        (Path::new("app5/update.tar.gz"), 0x31),
-        (Path::new("app6/update.spec"), 0x32),
-        (Path::new("app6/update.tar.gz"), 0x33),
-        (Path::new("app7/update.spec"), 0x34),
-        (Path::new("app7/update.tar.gz"), 0x35),
-    ];
     let mut out = HashMap::new();
 
-    for (p, i) in valid_paths.iter() {
+    for (p, i) in FILE_IDS.iter() {
         let full_path = dir.join(p);
         if full_path.exists() {
             out.insert(*i, full_path.into_os_string().into_string().unwrap());
@@ -207,6 +210,182 @@ impl Sequence for WriteFile {
     }
 }
 
+pub struct ReadFile;
+
+/// The local file of the [ReadFile] sequence.
+///
+/// The file is created with the first chunk and removed again if the
+/// sequence does not complete, e.x. on an abort, an error or if the stream is
+/// dropped.
+struct PartialFile {
+    path: PathBuf,
+    file: Option<std::fs::File>,
+    complete: bool,
+}
+
+impl PartialFile {
+    fn new(path: PathBuf) -> Self {
+        Self {
+            path,
+            file: None,
+            complete: false,
+        }
+    }
+
+    fn get_or_create(&mut self) -> std::io::Result<&std::fs::File> {
+        match self.file {
+            Some(ref file) => Ok(file),
+            None => Ok(self.file.insert(std::fs::File::create(&self.path)?)),
+        }
+    }
+
+    fn write_all_at(&mut self, payload: &[u8], offset: u64) -> std::io::Result<()> {
+        self.get_or_create()?.write_all_at(payload, offset)
+    }
+
+    /// Marks the file as complete. Creates it if the PT sent no data.
+    fn complete(&mut self) -> std::io::Result<()> {
+        self.get_or_create()?;
+        self.complete = true;
+        Ok(())
+    }
+}
+
+impl Drop for PartialFile {
+    fn drop(&mut self) {
+        if !self.complete && self.file.is_some() {
+            let _ = std::fs::remove_file(&self.path);
+        }
+    }
+}
+
+#[derive(Debug, ZvtEnum)]
+pub enum ReadFileResponse {
+    ReadFileCompletion(super::packets::ReadFileCompletion),
+    Abort(packets::Abort),
+}
+
+impl Sequence for ReadFile {
+    type Input = super::packets::ReadFileParameter;
+    type Output = ReadFileResponse;
+
+    fn into_stream<'a, Source>(
+        input: &'a Self::Input,
+        src: &'a mut PacketTransport<Source>,
+    ) -> Pin<Box<dyn Stream<Item = Result<Self::Output>> + Send + 'a>>
+    where
+        Source: AsyncReadExt + AsyncWriteExt + Unpin + Send,
+        Self: 'a,
+    {
+        let path = PathBuf::from(&input.path);
+        let password = input.password;
+        let file_id = input.file_id;
+        // The reverse of the WriteFile protocol:
+        // 1.1 ECR->PT: Request the file id at the given offset.
+        // 1.2 PT->ECR: Ack
+        // 2.1 PT->ECR: Completion with the chunk and the total file size.
+        // 2.2 ECR->PT: Ack
+        // The steps are repeated with the next offset until we have the entire
+        // file.
+
+        let s = try_stream! {
+            use super::packets::tlv::File as TlvFile;
+            let mut file = PartialFile::new(path);
+            let mut offset = 0;
+
+            loop {
+                let packet = super::packets::ReadFile {
+                    password,
+                    tlv: Some(super::packets::tlv::WriteData {
+                        file: Some(TlvFile {
+                            file_id: Some(file_id),
+                            file_offset: Some(offset),
+                            file_size: None,
+                            payload: None,
+                        }),
+                    }),
+                };
+
+                // 1.1 and 1.2
+                src.write_packet_with_ack(&packet).await?;
+
+                // 2.1 and 2.2
+                let response = src.read_packet().await?;
+                src.write_packet(&packets::Ack {}).await?;
+
+                match response {
+                    ReadFileResponse::Abort(_) => {
+                        yield response;
+                        break;
+                    }
+                    ReadFileResponse::ReadFileCompletion(ref data) => {
+                        let chunk = data
+                            .tlv
+                            .as_ref()
+                            .ok_or(ZVTError::IncompleteData)?
+                            .file
+                            .as_ref()
+                            .ok_or(ZVTError::IncompleteData)?;
+                        // Don't write a chunk of another file or offset.
+                        if chunk.file_id != Some(file_id) || chunk.file_offset != Some(offset) {
+                            Err(ZVTError::IncompleteData)?;
+                        }
+                        let payload = chunk.payload.as_deref().unwrap_or_default();
+                        file.write_all_at(payload, offset as u64)?;
+                        offset += payload.len() as u32;
+
+                        let done = payload.is_empty()
+                            || chunk.file_size.is_some_and(|size| offset >= size);
+                        if done {
+                            file.complete()?;
+                        }
+                        yield response;
+                        if done {
+                            break;
+                        }
+                    }
+                }
+            }
+        };
+        Box::pin(s)
+    }
+}
+
+pub struct DeleteFile;
+
+#[derive(Debug, ZvtEnum)]
+pub enum DeleteFileResponse {
+    CompletionData(packets::CompletionData),
+    Abort(packets::Abort),
+}
+
+impl Sequence for DeleteFile {
+    type Input = super::packets::DeleteFile;
+    type Output = DeleteFileResponse;
+}
+
+impl DeleteFile {
+    /// Returns the request deleting all files of the payload directory `dir`
+    /// from the PT.
+    ///
+    /// The files are mapped to their ids like in the [WriteFile] sequence.
+    pub fn request_for_dir(dir: &Path, password: usize) -> Result<super::packets::DeleteFile> {
+        let mut file_ids: Vec<_> = convert_dir(dir)?.into_keys().collect();
+        file_ids.sort();
+        let files = file_ids
+            .into_iter()
+            .map(|file_id| super::packets::tlv::File {
+                file_id: Some(file_id),
+                ..super::packets::tlv::File::default()
+            })
+            .collect();
+        Ok(super::packets::DeleteFile {
+            password,
+            tlv: Some(super::packets::tlv::WriteFile { files }),
+        })
+    }
+}
+
 pub struct FactoryReset;
 
 #[derive(Debug, ZvtEnum)]
@@ -219,15 +398,195 @@ impl Sequence for FactoryReset {
     type Output = FactoryResetResponse;
 }
 
-pub struct ChangeHostConfiguration;
+/// Change Configuration sequence for all configuration groups.
+///
+/// Build the input with the constructors of
+/// [super::packets::ChangeConfiguration], e.x.
+/// [super::packets::ChangeConfiguration::display].
+pub struct ChangeConfiguration;
 
 #[derive(Debug, ZvtEnum)]
-pub enum ChangeHostConfigurationResponse {
+pub enum ChangeConfigurationResponse {
     CompletionData(packets::CompletionData),
     Abort(packets::Abort),
 }
 
-impl Sequence for ChangeHostConfiguration {
+impl Sequence for ChangeConfiguration {
     type Input = super::packets::ChangeConfiguration;
-    type Output = ChangeHostConfigurationResponse;
+    type Output = ChangeConfigurationResponse;
+}
+
+#[deprecated(note = "Use ChangeConfiguration")]
+pub type ChangeHostConfiguration = ChangeConfiguration;
+
+#[deprecated(note = "Use ChangeConfigurationResponse")]
+pub type ChangeHostConfigurationResponse = ChangeConfigurationResponse;
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::ZvtSerializer;
+    use tokio::io::DuplexStream;
+    use tokio_stream::StreamExt;
+
+    /// Reads the next [super::super::packets::ReadFile] and returns the
+    /// requested offset.
+    async fn read_request(pt: &mut DuplexStream) -> u32 {
+        let mut buf = [0; 64];
+        pt.read_exact(&mut buf[..3]).await.unwrap();
+        let len = buf[2] as usize;
+        pt.read_exact(&mut buf[3..3 + len]).await.unwrap();
+        pt.write_all(&[0x80, 0x00, 0x00]).await.unwrap();
+        let (request, _) =
+            super::super::packets::ReadFile::zvt_deserialize(&buf[..3 + len]).unwrap();
+        request.tlv.unwrap().file.unwrap().file_offset.unwrap()
+    }
+
+    async fn send_response(pt: &mut DuplexStream, bytes: &[u8]) {
+        pt.write_all(bytes).await.unwrap();
+        let mut buf = [0; 3];
+        pt.read_exact(&mut buf).await.unwrap();
+        assert_eq!(buf, [0x80, 0x00, 0x00]);
+    }
+
+    fn chunk(offset: u32, payload: &[u8]) -> Vec<u8> {
+        chunk_of(0x13, offset, payload)
+    }
+
+    fn chunk_of(file_id: u8, offset: u32, payload: &[u8]) -> Vec<u8> {
+        super::super::packets::ReadFileCompletion {
+            tlv: Some(super::super::packets::tlv::WriteData {
+                file: Some(super::super::packets::tlv::File {
+                    file_id: Some(file_id),
+                    file_offset: Some(offset),
+                    file_size: Some(8),
+                    payload: Some(payload.to_vec()),
+                }),
+            }),
+        }
+        .zvt_serialize()
+    }
+
+    fn get_input(name: &str) -> super::super::packets::ReadFileParameter {
+        let path = std::env::temp_dir().join(format!("{}_{}", name, std::process::id()));
+        super::super::packets::ReadFileParameter {
+            path: path.to_str().unwrap().to_string(),
+            password: 123456,
+            file_id: 0x13,
+        }
+    }
+
+    #[tokio::test]
+    async fn test_read_file() {
+        let (ecr, mut pt) = tokio::io::duplex(256);
+        let pt = tokio::spawn(async move {
+            assert_eq!(read_request(&mut pt).await, 0);
+            send_response(&mut pt, &chunk(0, b"spec")).await;
+            assert_eq!(read_request(&mut pt).await, 4);
+            send_response(&mut pt, &chunk(4, b"file")).await;
+        });
+
+        let input = get_input("zvt_test_read_file");
+        let mut src = PacketTransport::new(ecr);
+        let mut stream = ReadFile::into_stream(&input, &mut src);
+        for _ in 0..2 {
+            assert!(matches!(
+                stream.next().await.unwrap().unwrap(),
+                ReadFileResponse::ReadFileCompletion(_)
+            ));
+        }
+        assert!(stream.next().await.is_none());
+        drop(stream);
+        pt.await.unwrap();
+
+        assert_eq!(std::fs::read(&input.path).unwrap(), b"specfile");
+        std::fs::remove_file(&input.path).unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_read_file_abort() {
+        let (ecr, mut pt) = tokio::io::duplex(256);
+        let pt = tokio::spawn(async move {
+            assert_eq!(read_request(&mut pt).await, 0);
+            send_response(&mut pt, &chunk(0, b"spec")).await;
+            assert_eq!(read_request(&mut pt).await, 4);
+            send_response(&mut pt, &[0x06, 0x1e, 0x01, 0x6c]).await;
+        });
+
+        let input = get_input("zvt_test_read_file_abort");
+        let mut src = PacketTransport::new(ecr);
+        let mut stream = ReadFile::into_stream(&input, &mut src);
+        assert!(matches!(
+            stream.next().await.unwrap().unwrap(),
+            ReadFileResponse::ReadFileCompletion(_)
+        ));
+        assert!(matches!(
+            stream.next().await.unwrap().unwrap(),
+            ReadFileResponse::Abort(_)
+        ));
+        assert!(stream.next().await.is_none());
+        drop(stream);
+        pt.await.unwrap();
+
+        assert!(!Path::new(&input.path).exists());
+    }
+
+    #[rstest::rstest]
+    fn test_delete_file_request_for_dir() {
+        let dir = std::env::temp_dir().join(format!("zvt_test_delete_file_{}", std::process::id()));
+        for path in [
+            "firmware/update.spec",
+            "app0/update.tar.gz",
+            "app0/unknown.txt",
+        ] {
+            let path = dir.join(path);
+            std::fs::create_dir_all(path.parent().unwrap()).unwrap();
+            std::fs::write(path, b"payload").unwrap();
+        }
+
+        let request = DeleteFile::request_for_dir(&dir, 123456).unwrap();
+        std::fs::remove_dir_all(&dir).unwrap();
+
+        let file_ids: Vec<_> = request
+            .tlv
+            .unwrap()
+            .files
+            .into_iter()
+            .map(|file| file.file_id)
+            .collect();
+        assert_eq!(request.password, 123456);
+        assert_eq!(file_ids, [Some(0x13), Some(0x21)]);
+        assert!(DeleteFile::request_for_dir(&dir, 123456).is_err());
+    }
+
+    #[rstest::rstest]
+    #[case::offset(0x13, 2)]
+    #[case::file_id(0x14, 4)]
+    #[tokio::test]
+    async fn test_read_file_mismatch(#[case] file_id: u8, #[case] offset: u32) {
+        let (ecr, mut pt) = tokio::io::duplex(256);
+        let pt = tokio::spawn(async move {
+            assert_eq!(read_request(&mut pt).await, 0);
+            send_response(&mut pt, &chunk(0, b"spec")).await;
+            assert_eq!(read_request(&mut pt).await, 4);
+            send_response(&mut pt, &chunk_of(file_id, offset, b"file")).await;
+        });
+
+        let input = get_input(&format!("zvt_test_read_file_mismatch_{file_id}"));
+        let mut src = PacketTransport::new(ecr);
+        let mut stream = ReadFile::into_stream(&input, &mut src);
+        assert!(matches!(
+            stream.next().await.unwrap().unwrap(),
+            ReadFileResponse::ReadFileCompletion(_)
+        ));
+        let err = stream.next().await.unwrap().unwrap_err();
+        assert_eq!(
+            err.downcast::<ZVTError>().unwrap(),
+            ZVTError::IncompleteData
+        );
+        drop(stream);
+        pt.await.unwrap();
+
+        assert!(!Path::new(&input.path).exists());
+    }
 }
@@ -1,7 +1,12 @@
+use crate::constants::ErrorMessages;
 use crate::packets;
+use crate::ZVTError;
 use crate::ZvtEnum;
 use crate::ZvtParser;
 use anyhow::Result;
+use num_traits::FromPrimitive;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
 use tokio::io::{AsyncReadExt, AsyncWriteExt};
 use zvt_builder::encoding;
 use zvt_builder::ZvtSerializer;
@@ -11,8 +16,107 @@ pub enum Ack {
     Ack(packets::Ack),
 }
 
+/// The error returned if the PT answers with a [packets::Nack].
+#[derive(Debug, PartialEq, thiserror::Error)]
+pub enum NackError {
+    #[error("Received a NACK: {0}")]
+    Known(ErrorMessages),
+
+    #[error("Received a NACK: unknown error 0x{0:02x}")]
+    Unknown(u8),
+}
+
+impl From<packets::Nack> for NackError {
+    fn from(nack: packets::Nack) -> Self {
+        match ErrorMessages::from_u8(nack.error) {
+            Some(msg) => Self::Known(msg),
+            None => Self::Unknown(nack.error),
+        }
+    }
+}
+
+/// Policy to repeat a command the PT rejected with a "please wait" NACK.
+///
+/// The PT answers with 84 9C ([ErrorMessages::PleaseWait]) if it's busy and
+/// the ECR may repeat the command after a short delay. Set `repeats` to zero
+/// to disable the retransmission.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct RetransmissionPolicy {
+    /// How often a command is repeated after the initial attempt.
+    pub repeats: usize,
+
+    /// The delay before repeating the command.
+    pub delay: Duration,
+}
+
+impl Default for RetransmissionPolicy {
+    fn default() -> Self {
+        Self {
+            repeats: 3,
+            delay: Duration::from_secs(1),
+        }
+    }
+}
+
+/// The timeout announced by the PT in an [packets::IntermediateStatusInformation].
+///
+/// The handle is shared with the [PacketTransport], so wrappers around a
+/// running sequence stream can extend their own timeouts accordingly, e.x.
+/// while the customer enters the PIN.
+#[derive(Debug, Clone, Default)]
+pub struct IntermediateTimeout(Arc<Mutex<Option<Duration>>>);
+
+impl IntermediateTimeout {
+    /// Returns the timeout for the next packet, if the PT announced one.
+    pub fn get(&self) -> Option<Duration> {
+        *self.0.lock().unwrap()
+    }
+
+    /// Overrides the timeout for the next packet.
+    pub fn set(&self, timeout: Option<Duration>) {
+        *self.0.lock().unwrap() = timeout;
+    }
+
+    fn take(&self) -> Option<Duration> {
+        self.0.lock().unwrap().take()
+    }
+}
+
+/// The timeout T3 for the [packets::Ack] after a command.
+pub const ACK_TIMEOUT: Duration = Duration::from_secs(5);
+
 pub struct PacketTransport<Source> {
     pub source: Source,
+    pub retransmission: RetransmissionPolicy,
+
+    /// How long we wait for the PT to acknowledge a command.
+    pub ack_timeout: Duration,
+
+    /// The timeout for the next packet as announced by the last
+    /// [packets::IntermediateStatusInformation].
+    read_timeout: IntermediateTimeout,
+
+    /// The first byte of the next packet, see [PacketTransport::wait_for_packet].
+    pending: Option<u8>,
+}
+
+impl<S> PacketTransport<S> {
+    /// Creates a new transport with the default [RetransmissionPolicy] and
+    /// the [ACK_TIMEOUT].
+    pub fn new(source: S) -> Self {
+        Self {
+            source,
+            retransmission: RetransmissionPolicy::default(),
+            ack_timeout: ACK_TIMEOUT,
+            read_timeout: IntermediateTimeout::default(),
+            pending: None,
+        }
+    }
+
+    /// Returns the handle to the timeout announced by the PT.
+    pub fn intermediate_timeout(&self) -> IntermediateTimeout {
+        self.read_timeout.clone()
+    }
 }
 
 impl<S> PacketTransport<S>
@@ -20,12 +124,79 @@ where
     S: AsyncReadExt + Unpin + Send,
 {
     /// Reads an ADPU packet from the PT.
+    ///
+    /// If the previous packet was a [packets::IntermediateStatusInformation]
+    /// with a timeout, we fail with [ZVTError::Timeout] if the packet doesn't
+    /// arrive in time. Otherwise we wait forever.
     pub async fn read_packet<T>(&mut self) -> Result<T>
     where
         T: ZvtParser + Send,
     {
+        let timeout = self.read_timeout.take();
+        let buf = self.read_bytes(timeout).await?;
+        Ok(T::zvt_parse(&buf)?)
+    }
+
+    /// Waits until the PT starts to send the next packet.
+    ///
+    /// Unlike [PacketTransport::read_packet] the call is cancel safe: If the
+    /// future is dropped, no data is lost. Use it to race the PT against other
+    /// events and read the packet afterwards.
+    pub async fn wait_for_packet(&mut self) -> Result<()> {
+        if self.pending.is_some() {
+            return Ok(());
+        }
+
+        let mut byte = [0; 1];
+        let read = match self.read_timeout.get() {
+            Some(timeout) => tokio::time::timeout(timeout, self.source.read(&mut byte))
+                .await
+                .map_err(|_| ZVTError::Timeout)??,
+            None => self.source.read(&mut byte).await?,
+        };
+        if read == 0 {
+            anyhow::bail!(ZVTError::IncompleteData);
+        }
+        self.pending = Some(byte[0]);
+        Ok(())
+    }
+
+    /// Reads the raw bytes of an ADPU packet from the PT.
+    ///
+    /// Fails with [ZVTError::Timeout] if the packet doesn't arrive within the
+    /// given timeout.
+    async fn read_bytes(&mut self, timeout: Option<Duration>) -> Result<Vec<u8>> {
+        let buf = match timeout {
+            Some(timeout) => tokio::time::timeout(timeout, self.read_adpu())
+                .await
+                .map_err(|_| ZVTError::Timeout)??,
+            None => self.read_adpu().await?,
+        };
+
+        // The intermediate status tells us how long to wait for the next
+        // packet, see chapter 2.2.4.
+        if let Ok((status, _)) = packets::IntermediateStatusInformation::zvt_deserialize(&buf) {
+            self.read_timeout.set(
+                status
+                    .timeout
+                    .filter(|timeout| *timeout != 0)
+                    .map(|timeout| Duration::from_secs(timeout as u64)),
+            );
+        }
+
+        Ok(buf)
+    }
+
+    async fn read_adpu(&mut self) -> Result<Vec<u8>> {
         let mut buf = vec![0; 3];
-        self.source.read_exact(&mut buf).await?;
+        let start = match self.pending.take() {
+            Some(byte) => {
+                buf[0] = byte;
+                1
+            }
+            None => 0,
+        };
+        self.source.read_exact(&mut buf[start..]).await?;
 
         // Get the len.
         let len = if buf[2] == 0xff {
@@ -42,7 +213,7 @@ where
 
         log::debug!("RX: {}", pretty_hex::simple_hex(&buf));
 
-        Ok(T::zvt_parse(&buf)?)
+        Ok(buf)
     }
 }
 
@@ -70,26 +241,175 @@ where
     S: AsyncWriteExt + AsyncReadExt + Unpin + Send,
 {
     /// Reads an ADPU packet from the PT and send an [packets::Ack].
+    ///
+    /// If the packet can't be parsed we answer with a [packets::Nack] and
+    /// return the parsing error.
     pub async fn read_packet_with_ack<'a, T>(&mut self) -> Result<T>
     where
         T: ZvtParser + Send,
     {
-        let packet = self.read_packet::<T>().await?;
-        self.write_packet(&packets::Ack {}).await?;
-
-        Ok(packet)
+        let timeout = self.read_timeout.take();
+        let buf = self.read_bytes(timeout).await?;
+        match T::zvt_parse(&buf) {
+            Ok(packet) => {
+                self.write_packet(&packets::Ack {}).await?;
+                Ok(packet)
+            }
+            Err(err) => {
+                self.write_packet(&packets::Nack {
+                    error: ErrorMessages::FunctionNotPossible as u8,
+                })
+                .await?;
+                Err(err.into())
+            }
+        }
     }
 
     /// Writes an ADPU packet to the PT and awaits its [packets::Ack].
+    ///
+    /// If the PT answers with a "please wait" [packets::Nack] the packet is
+    /// repeated as configured by the [RetransmissionPolicy]. Any other NACK,
+    /// or a "please wait" NACK after the last repetition, is returned as
+    /// [NackError]. If the PT doesn't answer within the `ack_timeout` we fail
+    /// with [ZVTError::Timeout].
     pub async fn write_packet_with_ack<'a, T>(&mut self, msg: &T) -> Result<()>
     where
         T: ZvtSerializer + Sync + Send,
         encoding::Default: encoding::Encoding<T>,
     {
-        self.write_packet(msg).await?;
+        let mut repeats = self.retransmission.repeats;
+        loop {
+            self.write_packet(msg).await?;
 
-        let _ = self.read_packet::<Ack>().await?;
+            let buf = self.read_bytes(Some(self.ack_timeout)).await?;
+            let nack = match packets::Nack::zvt_parse(&buf) {
+                Ok(nack) => NackError::from(nack),
+                Err(_) => {
+                    let _ = Ack::zvt_parse(&buf)?;
+                    return Ok(());
+                }
+            };
 
-        Ok(())
+            if nack != NackError::Known(ErrorMessages::PleaseWait) || repeats == 0 {
+                return Err(nack.into());
+            }
+            repeats -= 1;
+            log::info!(
+                "PT is busy, repeating the command in {:?}",
+                self.retransmission.delay
+            );
+            tokio::time::sleep(self.retransmission.delay).await;
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[derive(Debug, ZvtEnum)]
+    enum Status {
+        Status(packets::IntermediateStatusInformation),
+    }
+
+    #[tokio::test]
+    async fn test_write_packet_with_ack_nack() {
+        let (ecr, mut pt) = tokio::io::duplex(64);
+        let mut transport = PacketTransport::new(ecr);
+        transport.retransmission.repeats = 0;
+
+        let pt_task = tokio::spawn(async move {
+            let mut buf = [0; 3];
+            pt.read_exact(&mut buf).await.unwrap();
+            assert_eq!(buf, [0x80, 0x00, 0x00]);
+            pt.write_all(&[0x84, 0x9c, 0x00]).await.unwrap();
+        });
+
+        let err = transport
+            .write_packet_with_ack(&packets::Ack {})
+            .await
+            .unwrap_err();
+        assert_eq!(
+            err.downcast::<NackError>().unwrap(),
+            NackError::Known(ErrorMessages::PleaseWait)
+        );
+
+        pt_task.await.unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_write_packet_with_ack_retransmission() {
+        let (ecr, mut pt) = tokio::io::duplex(64);
+        let mut transport = PacketTransport::new(ecr);
+        transport.retransmission = RetransmissionPolicy {
+            repeats: 2,
+            delay: Duration::from_millis(1),
+        };
+
+        let pt_task = tokio::spawn(async move {
+            let mut buf = [0; 3];
+            for _ in 0..2 {
+                pt.read_exact(&mut buf).await.unwrap();
+                pt.write_all(&[0x84, 0x9c, 0x00]).await.unwrap();
+            }
+            pt.read_exact(&mut buf).await.unwrap();
+            assert_eq!(buf, [0x80, 0x00, 0x00]);
+            pt.write_all(&[0x80, 0x00, 0x00]).await.unwrap();
+        });
+
+        transport
+            .write_packet_with_ack(&packets::Ack {})
+            .await
+            .unwrap();
+
+        pt_task.await.unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_ack_timeout() {
+        let (ecr, _pt) = tokio::io::duplex(64);
+        let mut transport = PacketTransport::new(ecr);
+        transport.ack_timeout = Duration::from_millis(10);
+
+        let err = transport
+            .write_packet_with_ack(&packets::Ack {})
+            .await
+            .unwrap_err();
+        assert_eq!(err.downcast::<ZVTError>().unwrap(), ZVTError::Timeout);
+    }
+
+    #[tokio::test]
+    async fn test_intermediate_status_timeout() {
+        let (ecr, mut pt) = tokio::io::duplex(64);
+        let mut transport = PacketTransport::new(ecr);
+
+        // Intermediate status with a timeout of one second.
+        pt.write_all(&[0x04, 0xff, 0x02, 0x0a, 0x01]).await.unwrap();
+        let Status::Status(status) = transport.read_packet::<Status>().await.unwrap();
+        assert_eq!(status.timeout, Some(1));
+        assert_eq!(
+            transport.intermediate_timeout().get(),
+            Some(Duration::from_secs(1))
+        );
+
+        let err = transport.read_packet::<Status>().await.unwrap_err();
+        assert_eq!(err.downcast::<ZVTError>().unwrap(), ZVTError::Timeout);
+    }
+
+    #[tokio::test]
+    async fn test_read_packet_with_ack_sends_nack() {
+        let (ecr, mut pt) = tokio::io::duplex(64);
+        let mut transport = PacketTransport::new(ecr);
+
+        let pt_task = tokio::spawn(async move {
+            pt.write_all(&[0x06, 0x1e, 0x00]).await.unwrap();
+            let mut buf = [0; 3];
+            pt.read_exact(&mut buf).await.unwrap();
+            assert_eq!(buf, [0x84, 0x83, 0x00]);
+        });
+
+        assert!(transport.read_packet_with_ack::<Ack>().await.is_err());
+
+        pt_task.await.unwrap();
     }
 }
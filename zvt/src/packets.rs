@@ -1,4 +1,7 @@
-use crate::{encoding, length, Zvt};
+use crate::{
+    encoding, length, Tag, ZVTError, ZVTResult, Zvt, ZvtParser, ZvtSerializer, ZvtSerializerImpl,
+};
+use std::collections::HashMap;
 
 pub mod tlv;
 
@@ -57,6 +60,71 @@ pub struct SingleAmounts {
     pub others_total: usize,
 }
 
+/// The card brands of the [SingleAmounts].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum CardBrand {
+    Girocard,
+    Jcb,
+    Eurocard,
+    Amex,
+    Visa,
+    Diners,
+    Others,
+}
+
+/// The transactions of one [CardBrand] in the [SingleAmounts].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct BrandTotals {
+    /// The number of transactions.
+    pub count: u8,
+
+    /// The total amount in the fractional monetary unit.
+    pub total: usize,
+}
+
+impl SingleAmounts {
+    /// Returns the number of transactions and the total amount per card brand.
+    ///
+    /// Brands without transactions are omitted.
+    pub fn totals(&self) -> HashMap<CardBrand, BrandTotals> {
+        [
+            (CardBrand::Girocard, self.girocard_num, self.girocard_total),
+            (CardBrand::Jcb, self.jcb_num, self.jcb_total),
+            (CardBrand::Eurocard, self.eurocard_num, self.eurocard_total),
+            (CardBrand::Amex, self.amex_num, self.amex_total),
+            (CardBrand::Visa, self.visa_num, self.visa_total),
+            (CardBrand::Diners, self.diners_num, self.diners_total),
+            (CardBrand::Others, self.others_num, self.others_total),
+        ]
+        .into_iter()
+        .filter(|(_, count, _)| *count != 0)
+        .map(|(brand, count, total)| (brand, BrandTotals { count, total }))
+        .collect()
+    }
+}
+
+/// Custom encoding for strings of a fixed length of `N` bytes, padded with
+/// trailing zeros.
+///
+/// Used for BMP 3B, which carries the AID or the authorization code of a
+/// [TelephonicAuthorization]. The PT pads the AID with trailing zeros and
+/// expects it the same way when we send it back, e.x. in [BookTotal]. The
+/// [length::Fixed] length would pad it with leading zeros instead. Longer
+/// strings are truncated to `N` bytes.
+struct TrailingZeroPadded<const N: usize>;
+
+impl<const N: usize> encoding::Encoding<String> for TrailingZeroPadded<N> {
+    fn encode(input: &String) -> Vec<u8> {
+        let mut output = encoding::Default::encode(input);
+        output.resize(N, 0);
+        output
+    }
+
+    fn decode(data: &[u8]) -> ZVTResult<(String, &[u8])> {
+        encoding::Default::decode(data)
+    }
+}
+
 #[derive(Debug, Default, PartialEq, Zvt)]
 #[zvt_control_field(class = 0x04, instr = 0x0f)]
 pub struct StatusInformation {
@@ -96,7 +164,11 @@ pub struct StatusInformation {
     #[zvt_bmp(number = 0x2a, length = length::Fixed<15>)]
     pub vu_number: Option<String>,
 
-    #[zvt_bmp(number = 0x3b, length  = length::Fixed<8>)]
+    // The trace number of the original transaction, e.x. after a [Tip].
+    #[zvt_bmp(number = 0x37, length = length::Fixed<3>, encoding = encoding::Bcd)]
+    pub original_trace_number: Option<usize>,
+
+    #[zvt_bmp(number = 0x3b, length = length::Fixed<8>, encoding = TrailingZeroPadded<8>)]
     pub aid_authorization_attribute: Option<String>,
 
     #[zvt_bmp(number = 0x3c, length = length::Lllv)]
@@ -124,6 +196,15 @@ pub struct StatusInformation {
     pub tlv: Option<tlv::StatusInformation>,
 }
 
+impl StatusInformation {
+    /// Returns the balance of the card after a [BalanceRequest].
+    ///
+    /// The balance is in the smallest unit of the [Self::currency].
+    pub fn balance(&self) -> Option<usize> {
+        self.amount
+    }
+}
+
 #[derive(Debug, PartialEq, Zvt)]
 #[zvt_control_field(class = 0x04, instr = 0xff)]
 pub struct IntermediateStatusInformation {
@@ -133,6 +214,13 @@ pub struct IntermediateStatusInformation {
     pub timeout: Option<u8>,
 }
 
+impl IntermediateStatusInformation {
+    /// Returns the typed status or None if the code is unknown.
+    pub fn intermediate_status(&self) -> Option<crate::constants::IntermediateStatus> {
+        num_traits::FromPrimitive::from_u8(self.status)
+    }
+}
+
 /// Chapter 2.55
 #[derive(Debug, PartialEq, Zvt)]
 #[zvt_control_field(class = 0x05, instr = 0x01)]
@@ -204,6 +292,82 @@ pub struct Authorization {
     pub tlv: Option<tlv::AuthorizationData>,
 }
 
+/// Account Balance Request.
+///
+/// Queries the balance of a prepaid or closed-loop card. The PT returns the
+/// balance in the [StatusInformation].
+///
+/// See chapter 2.4.
+#[derive(Debug, Default, PartialEq, Zvt)]
+#[zvt_control_field(class = 0x06, instr = 0x03)]
+pub struct BalanceRequest {
+    #[zvt_bmp(number = 0x19)]
+    pub card_type: Option<u8>,
+
+    #[zvt_bmp(number = 0x06, length = length::Tlv)]
+    pub tlv: Option<tlv::ReadCard>,
+}
+
+/// Prepaid Top-Up.
+///
+/// Loads the given amount onto a prepaid card.
+///
+/// See chapter 2.7.
+#[derive(Debug, Default, PartialEq, Zvt)]
+#[zvt_control_field(class = 0x06, instr = 0x0a)]
+pub struct PrepaidTopUp {
+    #[zvt_bmp(number = 0x04, length = length::Fixed<6>, encoding = encoding::Bcd)]
+    pub amount: Option<usize>,
+
+    #[zvt_bmp(number = 0x49, length = length::Fixed<2>, encoding = encoding::Bcd)]
+    pub currency: Option<usize>,
+
+    #[zvt_bmp(number = 0x19)]
+    pub payment_type: Option<u8>,
+
+    #[zvt_bmp(number = 0x0e, length = length::Fixed<2>, encoding = encoding::Bcd)]
+    pub expiry_date: Option<usize>,
+
+    #[zvt_bmp(number = 0x22, length = length::Llv, encoding = encoding::Bcd)]
+    pub card_number: Option<usize>,
+
+    #[zvt_bmp(number = 0x23, length = length::Llv, encoding = encoding::Hex)]
+    pub track_2_data: Option<String>,
+
+    #[zvt_bmp(number = 0x8a)]
+    pub zvt_card_type: Option<u8>,
+
+    #[zvt_bmp(number = 0x06, length = length::Tlv)]
+    pub tlv: Option<tlv::AuthorizationData>,
+}
+
+/// Tip.
+///
+/// Adds a tip to an already authorized payment, identified by its receipt
+/// number. The amount is the tip only, not the new total.
+#[derive(Debug, Default, PartialEq, Zvt)]
+#[zvt_control_field(class = 0x06, instr = 0x0c)]
+pub struct Tip {
+    #[zvt_bmp(length = length::Fixed<3>, encoding = encoding::Bcd)]
+    pub password: usize,
+
+    #[zvt_bmp(number = 0x04, length = length::Fixed<6>, encoding = encoding::Bcd)]
+    pub amount: Option<usize>,
+
+    #[zvt_bmp(number = 0x87, length = length::Fixed<2>, encoding = encoding::Bcd)]
+    pub receipt_no: Option<usize>,
+
+    #[zvt_bmp(number = 0x49, length = length::Fixed<2>, encoding = encoding::Bcd)]
+    pub currency: Option<usize>,
+}
+
+/// Logs the ECR off from the PT.
+///
+/// See chapter 2.3.
+#[derive(Debug, PartialEq, Zvt)]
+#[zvt_control_field(class = 0x06, instr = 0x02)]
+pub struct LogOff {}
+
 #[derive(Debug, Default, PartialEq, Eq, Zvt)]
 #[zvt_control_field(class = 0x06, instr = 0x0f)]
 pub struct CompletionData {
@@ -232,6 +396,27 @@ pub struct ReceiptPrintoutCompletion {
     pub tlv: Option<tlv::ReceiptPrintoutCompletion>,
 }
 
+/// Send Turnover Totals.
+///
+/// The PT sends its current turnover without running an [EndOfDay].
+#[derive(Debug, PartialEq, Zvt)]
+#[zvt_control_field(class = 0x06, instr = 0x10)]
+pub struct SendTurnoverTotals {
+    #[zvt_bmp(length = length::Fixed<3>, encoding = encoding::Bcd)]
+    pub password: usize,
+}
+
+/// Print Turnover Receipts.
+///
+/// The PT prints the turnover receipts, or sends them to the ECR if it is
+/// the print target.
+#[derive(Debug, PartialEq, Zvt)]
+#[zvt_control_field(class = 0x06, instr = 0x12)]
+pub struct PrintTurnoverReceipts {
+    #[zvt_bmp(length = length::Fixed<3>, encoding = encoding::Bcd)]
+    pub password: usize,
+}
+
 /// Resets the terminal.
 ///
 /// See chapter 2.43.
@@ -291,6 +476,58 @@ pub struct PartialReversalAbort {
     // numbers. Produce the message to understand how it looks like.
 }
 
+/// Repeat Receipt.
+///
+/// The PT prints the receipt of the last transaction again. If the ECR prints
+/// the receipts, they are sent as [PrintLine] or [PrintTextBlock].
+#[derive(Debug, Default, PartialEq, Zvt)]
+#[zvt_control_field(class = 0x06, instr = 0x20)]
+pub struct RepeatReceipt {
+    #[zvt_bmp(length = length::Fixed<3>, encoding = encoding::Bcd)]
+    pub password: usize,
+
+    #[zvt_bmp(number = 0x03)]
+    pub service_byte: Option<u8>,
+}
+
+/// Telephonic Authorization.
+///
+/// Completes a payment which was declined with a referral to a voice
+/// authorization. The ECR sends the authorization code obtained by calling
+/// the acquirer.
+#[derive(Debug, Default, PartialEq, Zvt)]
+#[zvt_control_field(class = 0x06, instr = 0x21)]
+pub struct TelephonicAuthorization {
+    #[zvt_bmp(number = 0x04, length = length::Fixed<6>, encoding = encoding::Bcd)]
+    pub amount: Option<usize>,
+
+    #[zvt_bmp(number = 0x49, length = length::Fixed<2>, encoding = encoding::Bcd)]
+    pub currency: Option<usize>,
+
+    #[zvt_bmp(number = 0x19)]
+    pub payment_type: Option<u8>,
+
+    #[zvt_bmp(number = 0x0e, length = length::Fixed<2>, encoding = encoding::Bcd)]
+    pub expiry_date: Option<usize>,
+
+    #[zvt_bmp(number = 0x22, length = length::Llv, encoding = encoding::Bcd)]
+    pub card_number: Option<usize>,
+
+    #[zvt_bmp(number = 0x23, length = length::Llv, encoding = encoding::Hex)]
+    pub track_2_data: Option<String>,
+
+    // The authorization code from the acquirer, at most eight characters.
+    // Longer codes are truncated.
+    #[zvt_bmp(number = 0x3b, length = length::Fixed<8>, encoding = TrailingZeroPadded<8>)]
+    pub authorization_code: Option<String>,
+
+    #[zvt_bmp(number = 0x8a)]
+    pub zvt_card_type: Option<u8>,
+
+    #[zvt_bmp(number = 0x06, length = length::Tlv)]
+    pub tlv: Option<tlv::AuthorizationData>,
+}
+
 /// Pre-Authorization/Reservation.
 ///
 /// See chapter 2.8.
@@ -328,7 +565,7 @@ pub struct Reservation {
     #[zvt_bmp(number = 0x0b, length = length::Fixed<3>, encoding = encoding::Bcd)]
     pub trace_number: Option<usize>,
 
-    #[zvt_bmp(number = 0x3b, length = length::Fixed<8>)]
+    #[zvt_bmp(number = 0x3b, length = length::Fixed<8>, encoding = TrailingZeroPadded<8>)]
     pub aid_authorization_attribute: Option<String>,
 
     #[zvt_bmp(number = 0x3c, length = length::Lllv)]
@@ -388,6 +625,34 @@ pub struct PartialReversal {
     pub tlv: Option<tlv::PreAuthData>,
 }
 
+/// Book Total.
+///
+/// Books a [Reservation] with its final amount. See chapter 2.11.
+#[derive(Debug, Default, PartialEq, Zvt)]
+#[zvt_control_field(class = 0x06, instr = 0x24)]
+pub struct BookTotal {
+    #[zvt_bmp(number = 0x87, length = length::Fixed<2>, encoding = encoding::Bcd)]
+    pub receipt_no: Option<usize>,
+
+    #[zvt_bmp(number = 0x04, length = length::Fixed<6>, encoding = encoding::Bcd)]
+    pub amount: Option<usize>,
+
+    #[zvt_bmp(number = 0x49, length = length::Fixed<2>, encoding = encoding::Bcd)]
+    pub currency: Option<usize>,
+
+    #[zvt_bmp(number = 0x19)]
+    pub payment_type: Option<u8>,
+
+    #[zvt_bmp(number = 0x0b, length = length::Fixed<3>, encoding = encoding::Bcd)]
+    pub trace_number: Option<usize>,
+
+    #[zvt_bmp(number = 0x3b, length = length::Fixed<8>, encoding = TrailingZeroPadded<8>)]
+    pub aid_authorization_attribute: Option<String>,
+
+    #[zvt_bmp(number = 0x06, length = length::Tlv)]
+    pub tlv: Option<tlv::PreAuthData>,
+}
+
 #[derive(Debug, PartialEq, Zvt)]
 #[zvt_control_field(class = 0x06, instr = 0x25)]
 pub struct PreAuthReversal {
@@ -401,6 +666,56 @@ pub struct PreAuthReversal {
     pub receipt_no: Option<usize>,
 }
 
+/// Reversal of a completed payment.
+///
+/// See chapter 2.9.
+#[derive(Debug, Default, PartialEq, Zvt)]
+#[zvt_control_field(class = 0x06, instr = 0x30)]
+pub struct Reversal {
+    #[zvt_bmp(length = length::Fixed<3>, encoding = encoding::Bcd)]
+    pub password: usize,
+
+    #[zvt_bmp(number = 0x87, length = length::Fixed<2>, encoding = encoding::Bcd)]
+    pub receipt_no: Option<usize>,
+
+    #[zvt_bmp(number = 0x19)]
+    pub payment_type: Option<u8>,
+
+    #[zvt_bmp(number = 0x49, length = length::Fixed<2>, encoding = encoding::Bcd)]
+    pub currency: Option<usize>,
+}
+
+/// Refund.
+///
+/// See chapter 2.12.
+#[derive(Debug, Default, PartialEq, Zvt)]
+#[zvt_control_field(class = 0x06, instr = 0x31)]
+pub struct Refund {
+    #[zvt_bmp(length = length::Fixed<3>, encoding = encoding::Bcd)]
+    pub password: usize,
+
+    #[zvt_bmp(number = 0x04, length = length::Fixed<6>, encoding = encoding::Bcd)]
+    pub amount: Option<usize>,
+
+    #[zvt_bmp(number = 0x49, length = length::Fixed<2>, encoding = encoding::Bcd)]
+    pub currency: Option<usize>,
+
+    #[zvt_bmp(number = 0x19)]
+    pub payment_type: Option<u8>,
+
+    #[zvt_bmp(number = 0x0e, length = length::Fixed<2>, encoding = encoding::Bcd)]
+    pub expiry_date: Option<usize>,
+
+    #[zvt_bmp(number = 0x22, length = length::Llv, encoding = encoding::Bcd)]
+    pub card_number: Option<usize>,
+
+    #[zvt_bmp(number = 0x23, length = length::Llv, encoding= encoding::Hex)]
+    pub track_2_data: Option<String>,
+
+    #[zvt_bmp(number = 0x06, length = length::Tlv)]
+    pub tlv: Option<tlv::AuthorizationData>,
+}
+
 /// See chapter 2.16
 #[derive(Debug, PartialEq, Zvt)]
 #[zvt_control_field(class = 0x06, instr = 0x50)]
@@ -409,6 +724,16 @@ pub struct EndOfDay {
     pub password: usize,
 }
 
+/// Send Offline Transactions.
+///
+/// The PT uploads the transactions it stored while being offline to the host.
+#[derive(Debug, PartialEq, Zvt)]
+#[zvt_control_field(class = 0x06, instr = 0x51)]
+pub struct SendOfflineTransactions {
+    #[zvt_bmp(length = length::Fixed<3>, encoding = encoding::Bcd)]
+    pub password: usize,
+}
+
 /// Defined in 2.17.
 #[derive(Debug, PartialEq, Zvt)]
 #[zvt_control_field(class = 0x06, instr = 0x70)]
@@ -434,6 +759,27 @@ pub struct Initialization {
     pub password: usize,
 }
 
+/// Change Password.
+///
+/// Changes the merchant password of the PT.
+#[derive(Debug, PartialEq, Zvt)]
+#[zvt_control_field(class = 0x06, instr = 0x95)]
+pub struct ChangePassword {
+    #[zvt_bmp(length = length::Fixed<3>, encoding = encoding::Bcd)]
+    pub old_password: usize,
+
+    #[zvt_bmp(length = length::Fixed<3>, encoding = encoding::Bcd)]
+    pub new_password: usize,
+}
+
+/// Abort sent by the ECR.
+///
+/// With this command the ECR aborts a running sequence, e.x. while the PT is
+/// waiting for a card. The PT replies with an [Abort].
+#[derive(Debug, PartialEq, Zvt)]
+#[zvt_control_field(class = 0x06, instr = 0xb0)]
+pub struct EcrAbort {}
+
 #[derive(Debug, PartialEq, Zvt)]
 #[zvt_control_field(class = 0x06, instr = 0xc0)]
 pub struct ReadCard {
@@ -473,6 +819,148 @@ pub struct PrintTextBlock {
     pub tlv: Option<tlv::PrintTextBlock>,
 }
 
+/// Display Text.
+///
+/// Shows up to eight lines of text on the display of the PT. The PT only
+/// acknowledges the command, so use
+/// [crate::io::PacketTransport::write_packet_with_ack] to send it.
+#[derive(Debug, Default, PartialEq, Zvt)]
+#[zvt_control_field(class = 0x06, instr = 0xe0)]
+pub struct DisplayText {
+    // The display duration in seconds. 0 shows the text until the next
+    // command.
+    #[zvt_bmp(number = 0xf0)]
+    pub display_duration: Option<u8>,
+
+    #[zvt_bmp(number = 0xf1, length = length::Llv)]
+    pub line_1: Option<String>,
+
+    #[zvt_bmp(number = 0xf2, length = length::Llv)]
+    pub line_2: Option<String>,
+
+    #[zvt_bmp(number = 0xf3, length = length::Llv)]
+    pub line_3: Option<String>,
+
+    #[zvt_bmp(number = 0xf4, length = length::Llv)]
+    pub line_4: Option<String>,
+
+    #[zvt_bmp(number = 0xf5, length = length::Llv)]
+    pub line_5: Option<String>,
+
+    #[zvt_bmp(number = 0xf6, length = length::Llv)]
+    pub line_6: Option<String>,
+
+    #[zvt_bmp(number = 0xf7, length = length::Llv)]
+    pub line_7: Option<String>,
+
+    #[zvt_bmp(number = 0xf8, length = length::Llv)]
+    pub line_8: Option<String>,
+
+    #[zvt_bmp(number = 0xf9)]
+    pub beep_tones: Option<u8>,
+}
+
+/// Display Text with Numerical Input.
+///
+/// Shows the prompt on the display of the PT and lets the customer enter a
+/// number. The PT returns the input with [NumericalInputCompletion].
+#[derive(Debug, Default, PartialEq, Zvt)]
+#[zvt_control_field(class = 0x06, instr = 0xe2)]
+pub struct DisplayTextWithNumericalInput {
+    #[zvt_bmp(number = 0xf0)]
+    pub display_duration: Option<u8>,
+
+    #[zvt_bmp(number = 0xf1, length = length::Llv)]
+    pub line_1: Option<String>,
+
+    #[zvt_bmp(number = 0xf2, length = length::Llv)]
+    pub line_2: Option<String>,
+
+    #[zvt_bmp(number = 0xf3, length = length::Llv)]
+    pub line_3: Option<String>,
+
+    #[zvt_bmp(number = 0xf4, length = length::Llv)]
+    pub line_4: Option<String>,
+
+    #[zvt_bmp(number = 0xf5, length = length::Llv)]
+    pub line_5: Option<String>,
+
+    #[zvt_bmp(number = 0xf6, length = length::Llv)]
+    pub line_6: Option<String>,
+
+    #[zvt_bmp(number = 0xf7, length = length::Llv)]
+    pub line_7: Option<String>,
+
+    #[zvt_bmp(number = 0xf8, length = length::Llv)]
+    pub line_8: Option<String>,
+
+    #[zvt_bmp(number = 0xf9)]
+    pub beep_tones: Option<u8>,
+
+    #[zvt_bmp(number = 0xe0)]
+    pub min_length: Option<u8>,
+
+    #[zvt_bmp(number = 0xe1)]
+    pub max_length: Option<u8>,
+}
+
+/// Completion of [DisplayTextWithNumericalInput].
+#[derive(Debug, Default, PartialEq, Zvt)]
+#[zvt_control_field(class = 0x06, instr = 0x0f)]
+pub struct NumericalInputCompletion {
+    #[zvt_bmp(number = 0x27)]
+    pub result_code: Option<u8>,
+
+    // The digits entered by the customer.
+    #[zvt_bmp(number = 0xf1, length = length::Llv)]
+    pub input: Option<String>,
+}
+
+impl NumericalInputCompletion {
+    /// Returns the entered digits as a number.
+    ///
+    /// Returns `None` if the customer entered nothing or the input is not a
+    /// number.
+    pub fn value(&self) -> Option<usize> {
+        self.input.as_deref()?.parse().ok()
+    }
+}
+
+/// Blocked-List Query.
+///
+/// Asks the PT whether the presented card is on its blocked list. If the card
+/// is blocked, the PT replies with an [Abort] with error
+/// [crate::constants::ErrorMessages::CardInBlockedList].
+#[derive(Debug, Default, PartialEq, Zvt)]
+#[zvt_control_field(class = 0x06, instr = 0xe4)]
+pub struct BlockedListQuery {
+    #[zvt_bmp(number = 0x22, length = length::Llv, encoding = encoding::Bcd)]
+    pub card_number: Option<usize>,
+
+    #[zvt_bmp(number = 0x23, length = length::Llv, encoding = encoding::Hex)]
+    pub track_2_data: Option<String>,
+}
+
+/// Activate Service Mode.
+///
+/// Puts the PT into its service mode, e.x. to let a technician configure it.
+#[derive(Debug, PartialEq, Zvt)]
+#[zvt_control_field(class = 0x08, instr = 0x01)]
+pub struct ActivateServiceMode {
+    #[zvt_bmp(length = length::Fixed<3>, encoding = encoding::Bcd)]
+    pub password: usize,
+}
+
+/// Software Update.
+///
+/// The PT downloads and installs a new software from its host.
+#[derive(Debug, PartialEq, Zvt)]
+#[zvt_control_field(class = 0x08, instr = 0x10)]
+pub struct SoftwareUpdate {
+    #[zvt_bmp(length = length::Fixed<3>, encoding = encoding::Bcd)]
+    pub password: usize,
+}
+
 /// See chapter 2.36
 #[derive(Debug, PartialEq, Zvt)]
 #[zvt_control_field(class = 0x08, instr = 0x30)]
@@ -484,6 +972,53 @@ pub struct SelectLanguage {
 #[zvt_control_field(class = 0x80, instr = 0x00)]
 pub struct Ack {}
 
+/// Negative acknowledgement.
+///
+/// Unlike all other packets the NACK carries its error in the instruction
+/// byte (84 xx), e.x. 84 9C for [crate::constants::ErrorMessages::PleaseWait].
+/// Since the control field is not fixed we can't derive [Zvt] and implement
+/// the encoding by hand.
+#[derive(Debug, PartialEq)]
+pub struct Nack {
+    pub error: u8,
+}
+
+impl Nack {
+    pub const CLASS: u8 = 0x84;
+}
+
+impl encoding::Encoding<Nack> for encoding::Default {
+    fn encode(input: &Nack) -> Vec<u8> {
+        vec![Nack::CLASS, input.error, 0x00]
+    }
+
+    fn decode(bytes: &[u8]) -> ZVTResult<(Nack, &[u8])> {
+        if bytes.len() < 3 {
+            return Err(ZVTError::IncompleteData);
+        }
+        if bytes[0] != Nack::CLASS {
+            return Err(ZVTError::WrongTag(Tag(u16::from_be_bytes([
+                bytes[0], bytes[1],
+            ]))));
+        }
+        let (len, payload) = <length::Adpu as length::Length>::deserialize(&bytes[2..])?;
+        if len > payload.len() {
+            return Err(ZVTError::IncompleteData);
+        }
+        Ok((Nack { error: bytes[1] }, &payload[len..]))
+    }
+}
+
+impl ZvtSerializerImpl for Nack {}
+
+impl ZvtSerializer for Nack {}
+
+impl ZvtParser for Nack {
+    fn zvt_parse(bytes: &[u8]) -> ZVTResult<Self> {
+        Ok(Self::zvt_deserialize(bytes)?.0)
+    }
+}
+
 #[cfg(test)]
 pub mod tests {
     use super::*;
@@ -786,6 +1321,125 @@ pub mod tests {
         );
     }
 
+    #[rstest::rstest]
+    fn test_reversal() {
+        let bytes = get_bytes("reversal.blob");
+        let expected = Reversal {
+            password: 123456,
+            receipt_no: Some(231),
+            ..Reversal::default()
+        };
+        assert_eq!(Reversal::zvt_deserialize(&bytes).unwrap().0, expected);
+        assert_eq!(bytes, expected.zvt_serialize());
+    }
+
+    #[rstest::rstest]
+    fn test_repeat_receipt() {
+        let bytes = get_bytes("repeat_receipt.blob");
+        let expected = RepeatReceipt {
+            password: 123456,
+            service_byte: None,
+        };
+        assert_eq!(RepeatReceipt::zvt_deserialize(&bytes).unwrap().0, expected);
+        assert_eq!(bytes, expected.zvt_serialize());
+    }
+
+    #[rstest::rstest]
+    fn test_telephonic_authorization() {
+        let bytes = get_bytes("telephonic_authorization.blob");
+        let expected = TelephonicAuthorization {
+            amount: Some(2500),
+            currency: Some(978),
+            authorization_code: Some("12345678".to_string()),
+            ..TelephonicAuthorization::default()
+        };
+        assert_eq!(
+            TelephonicAuthorization::zvt_deserialize(&bytes).unwrap().0,
+            expected
+        );
+        assert_eq!(bytes, expected.zvt_serialize());
+
+        // Longer codes are truncated to eight bytes.
+        let truncated = TelephonicAuthorization {
+            authorization_code: Some("123456789".to_string()),
+            ..expected
+        };
+        assert_eq!(bytes, truncated.zvt_serialize());
+    }
+
+    #[rstest::rstest]
+    fn test_balance_request() {
+        let bytes = get_bytes("balance_request.blob");
+        let expected = BalanceRequest {
+            card_type: Some(0x40),
+            tlv: Some(tlv::ReadCard {
+                card_reading_control: None,
+                card_type: Some(0x05),
+            }),
+        };
+        assert_eq!(BalanceRequest::zvt_deserialize(&bytes).unwrap().0, expected);
+        assert_eq!(bytes, expected.zvt_serialize());
+
+        let bytes = get_bytes("balance_status_information.blob");
+        let packet = StatusInformation::zvt_deserialize(&bytes).unwrap().0;
+        assert_eq!(packet.balance(), Some(4250));
+        assert_eq!(packet.currency, Some(978));
+    }
+
+    #[rstest::rstest]
+    fn test_prepaid_top_up() {
+        let bytes = get_bytes("prepaid_top_up.blob");
+        let expected = PrepaidTopUp {
+            amount: Some(5000),
+            currency: Some(978),
+            payment_type: Some(0x40),
+            ..PrepaidTopUp::default()
+        };
+        assert_eq!(PrepaidTopUp::zvt_deserialize(&bytes).unwrap().0, expected);
+        assert_eq!(bytes, expected.zvt_serialize());
+    }
+
+    #[rstest::rstest]
+    fn test_tip() {
+        let bytes = get_bytes("tip.blob");
+        let expected = Tip {
+            password: 123456,
+            amount: Some(150),
+            receipt_no: Some(231),
+            currency: Some(978),
+        };
+        assert_eq!(Tip::zvt_deserialize(&bytes).unwrap().0, expected);
+        assert_eq!(bytes, expected.zvt_serialize());
+
+        let bytes = get_bytes("tip_status_information.blob");
+        let expected = StatusInformation {
+            result_code: Some(0),
+            amount: Some(150),
+            trace_number: Some(124),
+            original_trace_number: Some(123),
+            receipt_no: Some(231),
+            ..StatusInformation::default()
+        };
+        assert_eq!(
+            StatusInformation::zvt_deserialize(&bytes).unwrap().0,
+            expected
+        );
+    }
+
+    #[rstest::rstest]
+    fn test_refund() {
+        let bytes = get_bytes("refund.blob");
+        let expected = Refund {
+            password: 123456,
+            amount: Some(1000),
+            currency: Some(978),
+            payment_type: Some(0x40),
+            ..Refund::default()
+        };
+        assert_eq!(Refund::zvt_deserialize(&bytes).unwrap().0, expected);
+        assert_eq!(bytes, expected.zvt_serialize());
+    }
+
     #[rstest::rstest]
     fn test_completion_data() {
         let bytes = get_bytes("1680761818.641601000_pt_ecr.blob");
@@ -809,6 +1463,157 @@ pub mod tests {
         assert_eq!(bytes, expected.zvt_serialize());
     }
 
+    #[rstest::rstest]
+    fn test_send_offline_transactions() {
+        let bytes = get_bytes("send_offline_transactions.blob");
+        let expected = SendOfflineTransactions { password: 123456 };
+
+        assert_eq!(
+            SendOfflineTransactions::zvt_deserialize(&bytes).unwrap().0,
+            expected
+        );
+        assert_eq!(bytes, expected.zvt_serialize());
+    }
+
+    #[rstest::rstest]
+    fn test_activate_service_mode() {
+        let bytes = get_bytes("activate_service_mode.blob");
+        let expected = ActivateServiceMode { password: 123456 };
+
+        assert_eq!(
+            ActivateServiceMode::zvt_deserialize(&bytes).unwrap().0,
+            expected
+        );
+        assert_eq!(bytes, expected.zvt_serialize());
+    }
+
+    #[rstest::rstest]
+    fn test_software_update() {
+        let bytes = get_bytes("software_update.blob");
+        let expected = SoftwareUpdate { password: 123456 };
+
+        assert_eq!(SoftwareUpdate::zvt_deserialize(&bytes).unwrap().0, expected);
+        assert_eq!(bytes, expected.zvt_serialize());
+    }
+
+    #[rstest::rstest]
+    fn test_send_turnover_totals() {
+        let bytes = get_bytes("send_turnover_totals.blob");
+        let expected = SendTurnoverTotals { password: 123456 };
+
+        assert_eq!(
+            SendTurnoverTotals::zvt_deserialize(&bytes).unwrap().0,
+            expected
+        );
+        assert_eq!(bytes, expected.zvt_serialize());
+    }
+
+    #[rstest::rstest]
+    fn test_print_turnover_receipts() {
+        let bytes = get_bytes("print_turnover_receipts.blob");
+        let expected = PrintTurnoverReceipts { password: 123456 };
+
+        assert_eq!(
+            PrintTurnoverReceipts::zvt_deserialize(&bytes).unwrap().0,
+            expected
+        );
+        assert_eq!(bytes, expected.zvt_serialize());
+    }
+
+    #[rstest::rstest]
+    fn test_display_text() {
+        let bytes = get_bytes("display_text.blob");
+        let expected = DisplayText {
+            display_duration: Some(5),
+            line_1: Some("Hello".to_string()),
+            beep_tones: Some(1),
+            ..DisplayText::default()
+        };
+
+        assert_eq!(DisplayText::zvt_deserialize(&bytes).unwrap().0, expected);
+        assert_eq!(bytes, expected.zvt_serialize());
+    }
+
+    #[rstest::rstest]
+    fn test_display_text_with_numerical_input() {
+        let bytes = [
+            0x06, 0xe2, 0x0f, 0xf0, 0x1e, 0xf1, 0xf0, 0xf6, b'A', b'm', b'o', b'u', b'n', b't',
+            0xe0, 0x01, 0xe1, 0x04,
+        ];
+        let expected = DisplayTextWithNumericalInput {
+            display_duration: Some(30),
+            line_1: Some("Amount".to_string()),
+            min_length: Some(1),
+            max_length: Some(4),
+            ..DisplayTextWithNumericalInput::default()
+        };
+
+        assert_eq!(
+            DisplayTextWithNumericalInput::zvt_deserialize(&bytes)
+                .unwrap()
+                .0,
+            expected
+        );
+        assert_eq!(bytes.to_vec(), expected.zvt_serialize());
+    }
+
+    #[rstest::rstest]
+    fn test_numerical_input_completion() {
+        let bytes = get_bytes("numerical_input_completion.blob");
+        let expected = NumericalInputCompletion {
+            result_code: Some(0),
+            input: Some("0042".to_string()),
+        };
+
+        assert_eq!(
+            NumericalInputCompletion::zvt_deserialize(&bytes).unwrap().0,
+            expected
+        );
+        assert_eq!(bytes, expected.zvt_serialize());
+        assert_eq!(expected.value(), Some(42));
+        assert_eq!(NumericalInputCompletion::default().value(), None);
+    }
+
+    #[rstest::rstest]
+    fn test_change_password() {
+        let bytes = get_bytes("change_password.blob");
+        let expected = ChangePassword {
+            old_password: 123456,
+            new_password: 654321,
+        };
+
+        assert_eq!(ChangePassword::zvt_deserialize(&bytes).unwrap().0, expected);
+        assert_eq!(bytes, expected.zvt_serialize());
+    }
+
+    #[rstest::rstest]
+    fn test_nack() {
+        let bytes = [0x84, 0x9c, 0x00];
+        let expected = Nack { error: 0x9c };
+
+        assert_eq!(Nack::zvt_parse(&bytes).unwrap(), expected);
+        assert_eq!(bytes.to_vec(), expected.zvt_serialize());
+        assert!(Nack::zvt_parse(&[0x80, 0x00, 0x00]).is_err());
+    }
+
+    #[rstest::rstest]
+    fn test_single_amounts_totals() {
+        let bytes = get_bytes("1680761828.489701000_pt_ecr.blob");
+        let packet = StatusInformation::zvt_deserialize(&bytes).unwrap().0;
+        let totals = packet.single_amounts.unwrap().totals();
+
+        assert_eq!(
+            totals,
+            HashMap::from([(
+                CardBrand::Eurocard,
+                BrandTotals {
+                    count: 2,
+                    total: 958
+                }
+            )])
+        );
+    }
+
     #[rstest::rstest]
     fn test_partial_reversal() {
         let bytes = get_bytes("1681455683.221609000_ecr_pt.blob");
@@ -830,6 +1635,27 @@ pub mod tests {
         );
     }
 
+    #[rstest::rstest]
+    fn test_book_total() {
+        let bytes = get_bytes("book_total.blob");
+        let expected = BookTotal {
+            receipt_no: Some(231),
+            amount: Some(2500),
+            currency: Some(978),
+            payment_type: Some(0x40),
+            trace_number: Some(975),
+            aid_authorization_attribute: Some("750071".to_string()),
+            tlv: Some(tlv::PreAuthData {
+                bmp_data: Some(tlv::Bmp60 {
+                    bmp_prefix: "AC".to_string(),
+                    bmp_data: "384HH2".to_string(),
+                }),
+            }),
+        };
+        assert_eq!(BookTotal::zvt_deserialize(&bytes).unwrap().0, expected);
+        assert_eq!(bytes, expected.zvt_serialize());
+    }
+
     #[rstest::rstest]
     fn test_intermediate_status() {
         let bytes = get_bytes("1680728162.647465000_pt_ecr.blob");
@@ -844,6 +1670,11 @@ pub mod tests {
             expected
         );
         assert_eq!(bytes, expected.zvt_serialize());
+
+        let status = expected.intermediate_status().unwrap();
+        assert_eq!(status, crate::constants::IntermediateStatus::PleaseWait);
+        assert_eq!(status.to_string(), "Please wait...");
+        assert_eq!(format!("{status:#}"), "Bitte warten...");
     }
 
     #[rstest::rstest]
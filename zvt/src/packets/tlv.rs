@@ -128,7 +128,7 @@ pub struct Diagnosis {
     pub diagnosis_type: Option<u8>,
 }
 
-#[derive(Debug, PartialEq, Zvt)]
+#[derive(Debug, Default, PartialEq, Zvt)]
 pub struct ReadCard {
     #[zvt_tlv(tag = 0x1f15)]
     pub card_reading_control: Option<u8>,
@@ -1,14 +1,19 @@
+use crate::constants::ErrorMessages;
 use crate::io::PacketTransport;
 use crate::packets;
 use crate::{encoding, ZvtEnum, ZvtParser, ZvtSerializer};
 use anyhow::Result;
 use async_stream::try_stream;
-use futures::Stream;
+use futures::channel::oneshot;
+use futures::{FutureExt, Stream, StreamExt};
 use std::boxed::Box;
 use std::marker::Unpin;
 use std::pin::Pin;
 use tokio::io::{AsyncReadExt, AsyncWriteExt};
 
+/// The stream returned by [AbortableSequence::into_abortable_stream].
+pub type SequenceStream<'a, T> = Pin<Box<dyn Stream<Item = Result<T>> + Send + 'a>>;
+
 /// The trait for converting a sequence into a stream.
 ///
 /// What is written below? The [Self::Input] type must be a command as defined
@@ -20,8 +25,9 @@ use tokio::io::{AsyncReadExt, AsyncWriteExt};
 /// with [Send], so the stream can be shared between threads (or moved into
 /// [tokio::spawn], e.x.).
 ///
-/// The default implementation just waits for one message, acknowledges it and
-/// returns.
+/// The default implementation sends the [Self::Input], then acknowledges and
+/// returns the messages of the PT until [Sequence::is_final] marks the end of
+/// the sequence. By default this is the first message.
 pub trait Sequence
 where
     Self::Input: ZvtSerializer + Send + Sync,
@@ -42,13 +48,137 @@ where
             // This pin has nothing to do with the fact that we return a Stream
             // but is needed to access methods like `write_packet`.
             src.write_packet_with_ack(input).await?;
-            let packet = src.read_packet().await?;
-            // Write the response.
-            src.write_packet::<packets::Ack>(&packets::Ack {}).await?;
-            yield packet;
+            loop {
+                let packet = src.read_packet().await?;
+                // Write the response.
+                src.write_packet::<packets::Ack>(&packets::Ack {}).await?;
+                let is_final = Self::is_final(&packet);
+                yield packet;
+                if is_final {
+                    break;
+                }
+            }
         };
         Box::pin(s)
     }
+
+    /// Returns true if the `packet` ends the sequence, e.x. the
+    /// [packets::CompletionData] or the [packets::Abort].
+    fn is_final(_packet: &Self::Output) -> bool {
+        true
+    }
+}
+
+/// A [Sequence] which can be aborted by the ECR.
+///
+/// Only implemented for sequences following the default implementation of
+/// [Sequence::into_stream], e.x. the payment sequences. Sequences with their
+/// own exchange (e.x. [LogOff] or sending files) can't be aborted.
+pub trait AbortableSequence: Sequence
+where
+    Self::Input: ZvtSerializer + Send + Sync,
+    encoding::Default: encoding::Encoding<Self::Input>,
+{
+    /// Like [Sequence::into_stream] but the sequence can be aborted.
+    ///
+    /// Calling [AbortHandle::abort] sends an [packets::EcrAbort] to the PT.
+    /// The stream keeps yielding the packets the PT sends until
+    /// [Sequence::is_final] marks the end of the sequence, usually with an
+    /// [packets::Abort]. If the sequence has already finished, the abort is a
+    /// no-op.
+    ///
+    /// The abort is only sent while we wait for the next packet of the PT -
+    /// never while a packet is sent or received. An abort arriving before the
+    /// PT acknowledged the command is delayed until the ACK is received.
+    ///
+    /// The PT may reject the abort with a NACK, e.x. during the host
+    /// communication. In this case the stream continues with the sequence.
+    fn into_abortable_stream<'a, Source>(
+        input: &'a Self::Input,
+        src: &'a mut PacketTransport<Source>,
+    ) -> (AbortHandle, SequenceStream<'a, Self::Output>)
+    where
+        Source: AsyncReadExt + AsyncWriteExt + Unpin + Send,
+        Self: 'a,
+    {
+        let (sender, receiver) = oneshot::channel();
+        let s = try_stream! {
+            let mut receiver = receiver.fuse();
+            let mut aborted = false;
+            src.write_packet_with_ack(input).await?;
+            loop {
+                if !aborted {
+                    let abort = tokio::select! {
+                        biased;
+                        // Packets the PT already started to send go first.
+                        ready = src.wait_for_packet() => ready.map(|()| false),
+                        // A dropped handle is no abort.
+                        Ok(()) = &mut receiver => Ok(true),
+                    };
+                    if abort? {
+                        aborted = true;
+                        src.write_packet::<packets::EcrAbort>(&packets::EcrAbort {}).await?;
+                        continue;
+                    }
+                }
+
+                let packet = src.read_packet::<RawPacket>().await?;
+                // The answer to our abort. It may arrive after packets the PT
+                // sent before receiving the abort and must not be
+                // acknowledged.
+                if aborted && packet.is_ack() {
+                    continue;
+                }
+                if aborted && packet.is_nack() {
+                    log::warn!("Abort rejected by the PT: {:02x?}", packet.0);
+                    continue;
+                }
+                src.write_packet::<packets::Ack>(&packets::Ack {}).await?;
+                let packet = Self::Output::zvt_parse(&packet.0)?;
+                let is_final = Self::is_final(&packet);
+                yield packet;
+                if is_final {
+                    break;
+                }
+            }
+        };
+        (AbortHandle(sender), Box::pin(s))
+    }
+}
+
+/// Handle to abort a sequence from another task.
+///
+/// See [AbortableSequence::into_abortable_stream].
+#[derive(Debug)]
+pub struct AbortHandle(oneshot::Sender<()>);
+
+impl AbortHandle {
+    /// Aborts the sequence.
+    pub fn abort(self) {
+        // The receiver is gone if the stream was dropped - nothing to abort.
+        let _ = self.0.send(());
+    }
+}
+
+/// A packet which is read but not parsed yet.
+struct RawPacket(Vec<u8>);
+
+impl ZvtParser for RawPacket {
+    fn zvt_parse(bytes: &[u8]) -> zvt_builder::ZVTResult<Self> {
+        Ok(Self(bytes.to_vec()))
+    }
+}
+
+impl RawPacket {
+    /// Returns true for the [packets::Ack].
+    fn is_ack(&self) -> bool {
+        self.0[..2] == [0x80, 0x00]
+    }
+
+    /// Returns true for the [packets::Nack].
+    fn is_nack(&self) -> bool {
+        self.0[0] == packets::Nack::CLASS
+    }
 }
 
 /// Registration sequence as defined under 2.1.
@@ -61,6 +191,7 @@ pub struct Registration {}
 #[derive(Debug, ZvtEnum)]
 pub enum RegistrationResponse {
     CompletionData(packets::CompletionData),
+    Abort(packets::Abort),
 }
 
 impl Sequence for Registration {
@@ -68,6 +199,41 @@ impl Sequence for Registration {
     type Output = RegistrationResponse;
 }
 
+/// Log-Off sequence as defined under 2.3.
+///
+/// With this command the ECR logs off from the PT. Afterwards the ECR has to
+/// run a new [Registration] before sending further commands.
+pub struct LogOff;
+
+/// Response to [packets::LogOff] as defined under 2.3.
+///
+/// The PT only acknowledges the Log-Off, there is no Completion.
+#[derive(Debug, ZvtEnum)]
+pub enum LogOffResponse {
+    Ack(packets::Ack),
+}
+
+impl Sequence for LogOff {
+    type Input = packets::LogOff;
+    type Output = LogOffResponse;
+
+    fn into_stream<'a, Source>(
+        input: &'a Self::Input,
+        src: &'a mut PacketTransport<Source>,
+    ) -> Pin<Box<dyn Stream<Item = Result<Self::Output>> + Send + 'a>>
+    where
+        Source: AsyncReadExt + AsyncWriteExt + Unpin + Send,
+        Self: 'a,
+    {
+        let s = try_stream! {
+            // 2.3: The sequence ends with the PT's ACK.
+            src.write_packet_with_ack(input).await?;
+            yield LogOffResponse::Ack(packets::Ack {});
+        };
+        Box::pin(s)
+    }
+}
+
 /// Authorization sequence as defined under 2.1.
 ///
 /// Using the command Authorization the ECR initiates a payment transaction.
@@ -99,34 +265,16 @@ impl Sequence for Authorization {
     type Input = packets::Authorization;
     type Output = AuthorizationResponse;
 
-    fn into_stream<'a, Source>(
-        input: &'a Self::Input,
-        src: &'a mut PacketTransport<Source>,
-    ) -> Pin<Box<dyn Stream<Item = Result<Self::Output>> + Send + 'a>>
-    where
-        Source: AsyncReadExt + AsyncWriteExt + Unpin + Send,
-        Self: 'a,
-    {
-        let s = try_stream! {
-            // 2.1
-            src.write_packet_with_ack(input).await?;
-
-            loop {
-                let packet = src.read_packet().await?;
-                src.write_packet(&packets::Ack {}).await?;
-                match packet {
-                    AuthorizationResponse::CompletionData(_) | AuthorizationResponse::Abort(_) => {
-                        yield packet;
-                        break;
-                    }
-                    _ => yield packet,
-                }
-            }
-        };
-        Box::pin(s)
+    fn is_final(packet: &Self::Output) -> bool {
+        matches!(
+            packet,
+            AuthorizationResponse::CompletionData(_) | AuthorizationResponse::Abort(_)
+        )
     }
 }
 
+impl AbortableSequence for Authorization {}
+
 /// Read-card sequence as defined under 2.21.
 ///
 /// With this command the PT reads a chip-card/magnet-card and transmits the
@@ -146,34 +294,16 @@ impl Sequence for ReadCard {
     type Input = packets::ReadCard;
     type Output = ReadCardResponse;
 
-    fn into_stream<'a, Source>(
-        input: &'a Self::Input,
-        src: &'a mut PacketTransport<Source>,
-    ) -> Pin<Box<dyn futures::Stream<Item = Result<Self::Output>> + Send + 'a>>
-    where
-        Source: AsyncReadExt + AsyncWriteExt + Unpin + Send,
-        Self: 'a,
-    {
-        let s = try_stream! {
-            src.write_packet_with_ack(input).await?;
-            loop {
-                let packet = src.read_packet().await?;
-                // Write the response.
-                src.write_packet(&packets::Ack {}).await?;
-
-                match packet {
-                    ReadCardResponse::StatusInformation(_) | ReadCardResponse::Abort(_) => {
-                        yield packet;
-                        break;
-                    }
-                    _ => yield packet,
-                }
-            }
-        };
-        Box::pin(s)
+    fn is_final(packet: &Self::Output) -> bool {
+        matches!(
+            packet,
+            ReadCardResponse::StatusInformation(_) | ReadCardResponse::Abort(_)
+        )
     }
 }
 
+impl AbortableSequence for ReadCard {}
+
 /// Initialization sequence as defined under 2.18.
 ///
 /// The command forces the PT to send a initialization message to the ECR.
@@ -199,34 +329,11 @@ impl Sequence for Initialization {
     type Input = packets::Initialization;
     type Output = InitializationResponse;
 
-    fn into_stream<'a, Source>(
-        input: &'a Self::Input,
-        src: &'a mut PacketTransport<Source>,
-    ) -> Pin<Box<dyn Stream<Item = Result<Self::Output>> + Send + 'a>>
-    where
-        Source: AsyncReadExt + AsyncWriteExt + Unpin + Send,
-        Self: 'a,
-    {
-        let s = try_stream! {
-            // 2.18.1
-            src.write_packet_with_ack(input).await?;
-            loop {
-                let response = src.read_packet().await?;
-
-                // Every message requires an Ack.
-                src.write_packet(&packets::Ack {}).await?;
-
-                match response {
-                    InitializationResponse::CompletionData(_)
-                    | InitializationResponse::Abort(_) => {
-                        yield response;
-                        break;
-                    }
-                    _ => yield response,
-                }
-            }
-        };
-        Box::pin(s)
+    fn is_final(packet: &Self::Output) -> bool {
+        matches!(
+            packet,
+            InitializationResponse::CompletionData(_) | InitializationResponse::Abort(_)
+        )
     }
 }
 
@@ -252,6 +359,24 @@ impl Sequence for SetTerminalId {
     type Output = SetTerminalIdResponse;
 }
 
+/// Change Password sequence.
+///
+/// Causes the PT to replace its merchant password. All following commands
+/// which require a password must use the new one.
+pub struct ChangePassword;
+
+/// Response to [packets::ChangePassword] message.
+#[derive(Debug, ZvtEnum)]
+pub enum ChangePasswordResponse {
+    CompletionData(packets::CompletionData),
+    Abort(packets::Abort),
+}
+
+impl Sequence for ChangePassword {
+    type Input = packets::ChangePassword;
+    type Output = ChangePasswordResponse;
+}
+
 /// The Reset-terminal sequence, defined under 2.43.
 ///
 /// With this command the ECR causes the PT to restart.
@@ -304,34 +429,11 @@ impl Sequence for Diagnosis {
     type Input = packets::Diagnosis;
     type Output = DiagnosisResponse;
 
-    fn into_stream<'a, Source>(
-        input: &'a Self::Input,
-        src: &'a mut PacketTransport<Source>,
-    ) -> Pin<Box<dyn Stream<Item = Result<Self::Output>> + Send + 'a>>
-    where
-        Source: AsyncReadExt + AsyncWriteExt + Unpin + Send,
-        Self: 'a,
-    {
-        let s = try_stream! {
-            // 2.18.1
-            src.write_packet_with_ack(input).await?;
-            loop {
-                let response = src.read_packet().await?;
-
-                // Every message requires an Ack.
-                src.write_packet(&packets::Ack {}).await?;
-
-                match response {
-                    DiagnosisResponse::CompletionData(_)
-                    | DiagnosisResponse::Abort(_) => {
-                        yield response;
-                        break;
-                    }
-                    _ => yield response,
-                }
-            }
-        };
-        Box::pin(s)
+    fn is_final(packet: &Self::Output) -> bool {
+        matches!(
+            packet,
+            DiagnosisResponse::CompletionData(_) | DiagnosisResponse::Abort(_)
+        )
     }
 }
 
@@ -368,33 +470,66 @@ impl Sequence for EndOfDay {
     type Input = packets::EndOfDay;
     type Output = EndOfDayResponse;
 
-    fn into_stream<'a, Source>(
-        input: &'a Self::Input,
-        src: &'a mut PacketTransport<Source>,
-    ) -> Pin<Box<dyn Stream<Item = Result<Self::Output>> + Send + 'a>>
-    where
-        Source: AsyncReadExt + AsyncWriteExt + Unpin + Send,
-        Self: 'a,
-    {
-        let s = try_stream! {
-            // 2.16.1
-            src.write_packet_with_ack(input).await?;
+    fn is_final(packet: &Self::Output) -> bool {
+        matches!(
+            packet,
+            EndOfDayResponse::CompletionData(_) | EndOfDayResponse::Abort(_)
+        )
+    }
+}
 
-            loop {
-                let packet = src.read_packet().await?;
+/// Send Offline Transactions sequence.
+///
+/// With this command the ECR induces the PT to upload the transactions it
+/// stored while it had no connection to the host.
+pub struct SendOfflineTransactions;
 
-                // Write the response.
-                src.write_packet(&packets::Ack {}).await?;
-                match packet {
-                    EndOfDayResponse::CompletionData(_) | EndOfDayResponse::Abort(_) => {
-                        yield packet;
-                        break;
-                    }
-                    _ => yield packet,
-                }
-            }
-        };
-        Box::pin(s)
+/// Response to [packets::SendOfflineTransactions] message.
+#[derive(Debug, ZvtEnum)]
+pub enum SendOfflineTransactionsResponse {
+    IntermediateStatusInformation(packets::IntermediateStatusInformation),
+    CompletionData(packets::CompletionData),
+    Abort(packets::Abort),
+}
+
+impl Sequence for SendOfflineTransactions {
+    type Input = packets::SendOfflineTransactions;
+    type Output = SendOfflineTransactionsResponse;
+
+    fn is_final(packet: &Self::Output) -> bool {
+        matches!(
+            packet,
+            SendOfflineTransactionsResponse::CompletionData(_)
+                | SendOfflineTransactionsResponse::Abort(_)
+        )
+    }
+}
+
+/// Send Turnover Totals sequence.
+///
+/// With this command the ECR requests the current turnover of the PT. Unlike
+/// the [EndOfDay] the turnover is not transferred to the host and the totals
+/// are not reset. The totals are in [packets::StatusInformation::single_amounts].
+pub struct SendTurnoverTotals;
+
+/// Response to [packets::SendTurnoverTotals] message.
+#[derive(Debug, ZvtEnum)]
+#[allow(clippy::large_enum_variant)]
+pub enum SendTurnoverTotalsResponse {
+    StatusInformation(packets::StatusInformation),
+    CompletionData(packets::CompletionData),
+    Abort(packets::Abort),
+}
+
+impl Sequence for SendTurnoverTotals {
+    type Input = packets::SendTurnoverTotals;
+    type Output = SendTurnoverTotalsResponse;
+
+    fn is_final(packet: &Self::Output) -> bool {
+        matches!(
+            packet,
+            SendTurnoverTotalsResponse::CompletionData(_) | SendTurnoverTotalsResponse::Abort(_)
+        )
     }
 }
 
@@ -404,41 +539,23 @@ impl Sequence for EndOfDay {
 /// when the final payment-amount is only established after the authorization.
 /// In this case the ECR firstly reserves an amount (= maximal Possible
 /// payment-amount) and then, after the sales-process, releases the unused
-/// amount via a [PartialReversal] or Book Total (06 24, not implemented).
+/// amount via a [PartialReversal] or a [BookTotal].
 pub struct Reservation;
 
 impl Sequence for Reservation {
     type Input = packets::Reservation;
     type Output = AuthorizationResponse;
 
-    fn into_stream<'a, Source>(
-        input: &'a Self::Input,
-        src: &'a mut PacketTransport<Source>,
-    ) -> Pin<Box<dyn Stream<Item = Result<Self::Output>> + Send + 'a>>
-    where
-        Source: AsyncReadExt + AsyncWriteExt + Unpin + Send,
-        Self: 'a,
-    {
-        let s = try_stream! {
-            // 2.8
-            src.write_packet_with_ack(input).await?;
-
-            loop {
-                let packet = src.read_packet().await?;
-                src.write_packet(&packets::Ack {}).await?;
-                match packet {
-                    AuthorizationResponse::CompletionData(_) | AuthorizationResponse::Abort(_) => {
-                        yield packet;
-                        break;
-                    }
-                    _ => yield packet,
-                }
-            }
-        };
-        Box::pin(s)
+    fn is_final(packet: &Self::Output) -> bool {
+        matches!(
+            packet,
+            AuthorizationResponse::CompletionData(_) | AuthorizationResponse::Abort(_)
+        )
     }
 }
 
+impl AbortableSequence for Reservation {}
+
 /// Partial reversal sequence as defined under 2.10.
 ///
 /// This command executes a Partial-Reversal for a [Reservation] to release the
@@ -480,34 +597,40 @@ impl Sequence for PartialReversal {
     type Input = packets::PartialReversal;
     type Output = PartialReversalResponse;
 
-    fn into_stream<'a, Source>(
-        input: &'a Self::Input,
-        src: &'a mut PacketTransport<Source>,
-    ) -> Pin<Box<dyn Stream<Item = Result<Self::Output>> + Send + 'a>>
-    where
-        Source: AsyncReadExt + AsyncWriteExt + Unpin + Send,
-        Self: 'a,
-    {
-        let s = try_stream! {
-            src.write_packet_with_ack(input).await?;
+    fn is_final(packet: &Self::Output) -> bool {
+        matches!(
+            packet,
+            PartialReversalResponse::CompletionData(_)
+                | PartialReversalResponse::PartialReversalAbort(_)
+        )
+    }
+}
 
-            loop {
-                let packet = src.read_packet().await?;
-                src.write_packet(&packets::Ack {}).await?;
-                match packet {
-                    PartialReversalResponse::CompletionData(_)
-                    | PartialReversalResponse::PartialReversalAbort(_) => {
-                        yield packet;
-                        break;
-                    }
-                    _ => yield packet,
-                }
-            }
-        };
-        Box::pin(s)
+impl AbortableSequence for PartialReversal {}
+
+/// Book total sequence as defined under 2.11.
+///
+/// This command books a [Reservation] with its final amount. Like the
+/// [PartialReversal] it releases the unused amount of the reservation, but
+/// some acquirers only accept this command. The sequence is identical to the
+/// [PartialReversal].
+pub struct BookTotal;
+
+impl Sequence for BookTotal {
+    type Input = packets::BookTotal;
+    type Output = PartialReversalResponse;
+
+    fn is_final(packet: &Self::Output) -> bool {
+        matches!(
+            packet,
+            PartialReversalResponse::CompletionData(_)
+                | PartialReversalResponse::PartialReversalAbort(_)
+        )
     }
 }
 
+impl AbortableSequence for BookTotal {}
+
 /// Pre-Auth-Reversal sequence as defined in 2.14
 ///
 /// This command executes a reversal of a [Reservation] in the case of a
@@ -518,31 +641,171 @@ impl Sequence for PreAuthReversal {
     type Input = packets::PreAuthReversal;
     type Output = PartialReversalResponse;
 
-    fn into_stream<'a, Source>(
-        input: &'a Self::Input,
-        src: &'a mut PacketTransport<Source>,
-    ) -> Pin<Box<dyn Stream<Item = Result<Self::Output>> + Send + 'a>>
-    where
-        Source: AsyncReadExt + AsyncWriteExt + Unpin + Send,
-        Self: 'a,
-    {
-        let s = try_stream! {
-            src.write_packet_with_ack(input).await?;
+    fn is_final(packet: &Self::Output) -> bool {
+        matches!(
+            packet,
+            PartialReversalResponse::CompletionData(_)
+                | PartialReversalResponse::PartialReversalAbort(_)
+        )
+    }
+}
 
-            loop {
-                let packet = src.read_packet().await?;
-                src.write_packet(&packets::Ack {}).await?;
-                match packet {
-                    PartialReversalResponse::CompletionData(_)
-                    | PartialReversalResponse::PartialReversalAbort(_) => {
-                        yield packet;
-                        break;
-                    }
-                    _ => yield packet,
-                }
-            }
-        };
-        Box::pin(s)
+impl AbortableSequence for PreAuthReversal {}
+
+/// Reversal sequence as defined under 2.9.
+///
+/// This command reverses a payment of an [Authorization]. The payment is
+/// identified by the receipt number returned in
+/// [packets::StatusInformation::receipt_no]. The sequence is identical to the
+/// [Authorization]. If the payment cannot be reversed, the PT sends an
+/// [packets::Abort] with either
+/// [crate::constants::ErrorMessages::AlreadyReversed] or
+/// [crate::constants::ErrorMessages::ReversalNotPossible].
+pub struct Reversal;
+
+impl Sequence for Reversal {
+    type Input = packets::Reversal;
+    type Output = AuthorizationResponse;
+
+    fn is_final(packet: &Self::Output) -> bool {
+        matches!(
+            packet,
+            AuthorizationResponse::CompletionData(_) | AuthorizationResponse::Abort(_)
+        )
+    }
+}
+
+impl AbortableSequence for Reversal {}
+
+/// Balance Request sequence as defined under 2.4.
+///
+/// With this command the ECR queries the balance of a prepaid or closed-loop
+/// card. The sequence is identical to the [Authorization]; the balance is
+/// returned via [packets::StatusInformation::balance].
+pub struct BalanceRequest;
+
+impl Sequence for BalanceRequest {
+    type Input = packets::BalanceRequest;
+    type Output = AuthorizationResponse;
+
+    fn is_final(packet: &Self::Output) -> bool {
+        matches!(
+            packet,
+            AuthorizationResponse::CompletionData(_) | AuthorizationResponse::Abort(_)
+        )
+    }
+}
+
+impl AbortableSequence for BalanceRequest {}
+
+/// Prepaid Top-Up sequence as defined under 2.7.
+///
+/// With this command the ECR loads value onto a prepaid card. The sequence is
+/// identical to the [Authorization].
+pub struct PrepaidTopUp;
+
+impl Sequence for PrepaidTopUp {
+    type Input = packets::PrepaidTopUp;
+    type Output = AuthorizationResponse;
+
+    fn is_final(packet: &Self::Output) -> bool {
+        matches!(
+            packet,
+            AuthorizationResponse::CompletionData(_) | AuthorizationResponse::Abort(_)
+        )
+    }
+}
+
+impl AbortableSequence for PrepaidTopUp {}
+
+/// Tip sequence.
+///
+/// With this command the ECR adds a tip to an authorized payment. The sequence
+/// is identical to the [Authorization]; the [packets::StatusInformation] refers
+/// to the original payment via its `original_trace_number`.
+pub struct Tip;
+
+impl Sequence for Tip {
+    type Input = packets::Tip;
+    type Output = AuthorizationResponse;
+
+    fn is_final(packet: &Self::Output) -> bool {
+        matches!(
+            packet,
+            AuthorizationResponse::CompletionData(_) | AuthorizationResponse::Abort(_)
+        )
+    }
+}
+
+impl AbortableSequence for Tip {}
+
+/// Refund sequence as defined under 2.12.
+///
+/// With this command the ECR initiates a refund of the given amount to a card.
+/// The sequence is identical to the [Authorization].
+pub struct Refund;
+
+impl Sequence for Refund {
+    type Input = packets::Refund;
+    type Output = AuthorizationResponse;
+
+    fn is_final(packet: &Self::Output) -> bool {
+        matches!(
+            packet,
+            AuthorizationResponse::CompletionData(_) | AuthorizationResponse::Abort(_)
+        )
+    }
+}
+
+impl AbortableSequence for Refund {}
+
+/// Telephonic Authorization sequence.
+///
+/// Used after an [Authorization] was aborted with error 0x02 ("declined,
+/// referred voice authorization possible") and the merchant obtained an
+/// authorization code from the acquirer. The sequence is identical to the
+/// [Authorization].
+pub struct TelephonicAuthorization;
+
+impl Sequence for TelephonicAuthorization {
+    type Input = packets::TelephonicAuthorization;
+    type Output = AuthorizationResponse;
+
+    fn is_final(packet: &Self::Output) -> bool {
+        matches!(
+            packet,
+            AuthorizationResponse::CompletionData(_) | AuthorizationResponse::Abort(_)
+        )
+    }
+}
+
+impl AbortableSequence for TelephonicAuthorization {}
+
+/// Repeat Receipt sequence.
+///
+/// With this command the ECR requests a copy of the last receipt. The PT
+/// either prints it itself or sends it to the ECR, depending on the print
+/// target defined in [Registration].
+pub struct RepeatReceipt;
+
+/// Response to [packets::RepeatReceipt] message.
+#[derive(Debug, ZvtEnum)]
+pub enum RepeatReceiptResponse {
+    PrintLine(packets::PrintLine),
+    PrintTextBlock(packets::PrintTextBlock),
+    CompletionData(packets::CompletionData),
+    Abort(packets::Abort),
+}
+
+impl Sequence for RepeatReceipt {
+    type Input = packets::RepeatReceipt;
+    type Output = RepeatReceiptResponse;
+
+    fn is_final(packet: &Self::Output) -> bool {
+        matches!(
+            packet,
+            RepeatReceiptResponse::CompletionData(_) | RepeatReceiptResponse::Abort(_)
+        )
     }
 }
 
@@ -567,30 +830,191 @@ impl Sequence for PrintSystemConfiguration {
     type Input = packets::PrintSystemConfiguration;
     type Output = PrintSystemConfigurationResponse;
 
-    fn into_stream<'a, Source>(
-        input: &'a Self::Input,
-        src: &'a mut PacketTransport<Source>,
-    ) -> Pin<Box<dyn Stream<Item = Result<Self::Output>> + Send + 'a>>
+    fn is_final(packet: &Self::Output) -> bool {
+        matches!(packet, PrintSystemConfigurationResponse::CompletionData(_))
+    }
+}
+
+/// Prints the turnover receipts.
+///
+/// With this command the ECR causes the PT to print the turnover receipts to
+/// the print target defined in [Registration]. Unlike the [EndOfDay] the
+/// turnover is not transferred to the host.
+pub struct PrintTurnoverReceipts;
+
+/// Response to [packets::PrintTurnoverReceipts] message.
+#[derive(Debug, ZvtEnum)]
+pub enum PrintTurnoverReceiptsResponse {
+    PrintLine(packets::PrintLine),
+    PrintTextBlock(packets::PrintTextBlock),
+    CompletionData(packets::CompletionData),
+    Abort(packets::Abort),
+}
+
+impl Sequence for PrintTurnoverReceipts {
+    type Input = packets::PrintTurnoverReceipts;
+    type Output = PrintTurnoverReceiptsResponse;
+
+    fn is_final(packet: &Self::Output) -> bool {
+        matches!(
+            packet,
+            PrintTurnoverReceiptsResponse::CompletionData(_)
+                | PrintTurnoverReceiptsResponse::Abort(_)
+        )
+    }
+}
+
+/// Display Text with Numerical Input sequence.
+///
+/// With this command the ECR lets the customer enter a number on the PT, e.x.
+/// the number of the charge point. The entered digits are in
+/// [packets::NumericalInputCompletion::input].
+pub struct DisplayTextWithNumericalInput;
+
+/// Response to [packets::DisplayTextWithNumericalInput] message.
+#[derive(Debug, ZvtEnum)]
+pub enum DisplayTextWithNumericalInputResponse {
+    IntermediateStatusInformation(packets::IntermediateStatusInformation),
+    CompletionData(packets::NumericalInputCompletion),
+    Abort(packets::Abort),
+}
+
+impl Sequence for DisplayTextWithNumericalInput {
+    type Input = packets::DisplayTextWithNumericalInput;
+    type Output = DisplayTextWithNumericalInputResponse;
+
+    fn is_final(packet: &Self::Output) -> bool {
+        matches!(
+            packet,
+            DisplayTextWithNumericalInputResponse::CompletionData(_)
+                | DisplayTextWithNumericalInputResponse::Abort(_)
+        )
+    }
+}
+
+/// Blocked-List Query sequence.
+///
+/// With this command the ECR asks the PT whether the presented card is on the
+/// blocked list. Use [BlockedListQuery::query] to get the result as a
+/// [BlockedListStatus].
+pub struct BlockedListQuery;
+
+/// Response to [packets::BlockedListQuery] message.
+#[derive(Debug, ZvtEnum)]
+#[allow(clippy::large_enum_variant)]
+pub enum BlockedListQueryResponse {
+    IntermediateStatusInformation(packets::IntermediateStatusInformation),
+    StatusInformation(packets::StatusInformation),
+    CompletionData(packets::CompletionData),
+    Abort(packets::Abort),
+}
+
+impl Sequence for BlockedListQuery {
+    type Input = packets::BlockedListQuery;
+    type Output = BlockedListQueryResponse;
+
+    fn is_final(packet: &Self::Output) -> bool {
+        matches!(
+            packet,
+            BlockedListQueryResponse::CompletionData(_) | BlockedListQueryResponse::Abort(_)
+        )
+    }
+}
+
+/// The result of the [BlockedListQuery].
+#[derive(Debug)]
+pub struct BlockedListStatus {
+    /// True if the card is on the blocked list.
+    pub blocked: bool,
+
+    /// The card data, if the PT sent it.
+    pub status_information: Option<packets::StatusInformation>,
+}
+
+impl BlockedListQuery {
+    /// Runs the sequence and maps the responses to a [BlockedListStatus].
+    ///
+    /// Aborts other than
+    /// [crate::constants::ErrorMessages::CardInBlockedList] are returned as
+    /// [crate::ZVTError::Aborted].
+    pub async fn query<Source>(
+        input: &packets::BlockedListQuery,
+        src: &mut PacketTransport<Source>,
+    ) -> Result<BlockedListStatus>
     where
         Source: AsyncReadExt + AsyncWriteExt + Unpin + Send,
-        Self: 'a,
     {
-        let s = try_stream! {
-            src.write_packet_with_ack(input).await?;
-
-            loop {
-                let packet = src.read_packet().await?;
-                src.write_packet(&packets::Ack {}).await?;
-                match packet {
-                    PrintSystemConfigurationResponse::CompletionData(_) => {
-                        yield packet;
-                        break;
+        let mut status = BlockedListStatus {
+            blocked: false,
+            status_information: None,
+        };
+        let mut stream = Self::into_stream(input, src);
+        while let Some(response) = stream.next().await {
+            match response? {
+                BlockedListQueryResponse::IntermediateStatusInformation(_) => (),
+                BlockedListQueryResponse::StatusInformation(data) => {
+                    status.status_information = Some(data)
+                }
+                BlockedListQueryResponse::CompletionData(_) => return Ok(status),
+                BlockedListQueryResponse::Abort(data) => {
+                    if data.error != ErrorMessages::CardInBlockedList as u8 {
+                        anyhow::bail!(crate::ZVTError::Aborted(data.error));
                     }
-                    _ => yield packet,
+                    status.blocked = true;
+                    return Ok(status);
                 }
             }
-        };
-        Box::pin(s)
+        }
+        Err(crate::ZVTError::IncompleteData.into())
+    }
+}
+
+/// Activate Service Mode sequence.
+///
+/// With this command the ECR puts the PT into its service mode. Unlike the
+/// Feig specific commands under [crate::feig] this is part of the generic ZVT
+/// specification.
+pub struct ActivateServiceMode;
+
+/// Response to [packets::ActivateServiceMode] message.
+#[derive(Debug, ZvtEnum)]
+pub enum ActivateServiceModeResponse {
+    CompletionData(packets::CompletionData),
+    Abort(packets::Abort),
+}
+
+impl Sequence for ActivateServiceMode {
+    type Input = packets::ActivateServiceMode;
+    type Output = ActivateServiceModeResponse;
+}
+
+/// Software Update sequence.
+///
+/// With this command the ECR causes the PT to download and install a new
+/// software from its host. Unlike [crate::feig::sequences::WriteFile] the ECR
+/// does not transfer any files. The PT may restart after the
+/// [packets::CompletionData], which drops the connection.
+pub struct SoftwareUpdate;
+
+/// Response to [packets::SoftwareUpdate] message.
+#[derive(Debug, ZvtEnum)]
+pub enum SoftwareUpdateResponse {
+    IntermediateStatusInformation(packets::IntermediateStatusInformation),
+    PrintLine(packets::PrintLine),
+    PrintTextBlock(packets::PrintTextBlock),
+    CompletionData(packets::CompletionData),
+    Abort(packets::Abort),
+}
+
+impl Sequence for SoftwareUpdate {
+    type Input = packets::SoftwareUpdate;
+    type Output = SoftwareUpdateResponse;
+
+    fn is_final(packet: &Self::Output) -> bool {
+        matches!(
+            packet,
+            SoftwareUpdateResponse::CompletionData(_) | SoftwareUpdateResponse::Abort(_)
+        )
     }
 }
 
@@ -637,29 +1061,282 @@ impl Sequence for StatusEnquiry {
     type Input = packets::StatusEnquiry;
     type Output = StatusEnquiryResponse;
 
-    fn into_stream<'a, Source>(
-        input: &'a Self::Input,
-        src: &'a mut PacketTransport<Source>,
-    ) -> Pin<Box<dyn Stream<Item = Result<Self::Output>> + Send + 'a>>
-    where
-        Source: AsyncReadExt + AsyncWriteExt + Unpin + Send,
-        Self: 'a,
-    {
-        let s = try_stream! {
-            src.write_packet_with_ack(input).await?;
+    fn is_final(packet: &Self::Output) -> bool {
+        matches!(packet, StatusEnquiryResponse::CompletionData(_))
+    }
+}
 
-            loop {
-                let packet = src.read_packet().await?;
-                src.write_packet(&packets::Ack {}).await?;
-                match packet {
-                    StatusEnquiryResponse::CompletionData(_) => {
-                        yield packet;
-                        break;
-                    }
-                    _ => yield packet,
-                }
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::packets::tests::get_bytes;
+
+    #[rstest::rstest]
+    fn test_authorization_response() {
+        let bytes = get_bytes("1680728162.647465000_pt_ecr.blob");
+        let packet = AuthorizationResponse::zvt_parse(&bytes).unwrap();
+        assert!(matches!(
+            packet,
+            AuthorizationResponse::IntermediateStatusInformation(_)
+        ));
+
+        let bytes = get_bytes("1680728165.675509000_pt_ecr.blob");
+        let packet = AuthorizationResponse::zvt_parse(&bytes).unwrap();
+        assert!(matches!(
+            packet,
+            AuthorizationResponse::StatusInformation(_)
+        ));
+
+        let bytes = get_bytes("1680728215.585561000_pt_ecr.blob");
+        let packet = AuthorizationResponse::zvt_parse(&bytes).unwrap();
+        assert!(matches!(packet, AuthorizationResponse::PrintTextBlock(_)));
+
+        let bytes = get_bytes("1680728165.827009000_pt_ecr.blob");
+        let packet = AuthorizationResponse::zvt_parse(&bytes).unwrap();
+        assert!(matches!(
+            packet,
+            AuthorizationResponse::CompletionData(packets::CompletionData {
+                result_code: None,
+                status_byte: None,
+                terminal_id: None,
+                currency: None,
+            })
+        ));
+    }
+
+    #[rstest::rstest]
+    fn test_reversal_abort() {
+        use crate::constants::ErrorMessages;
+        use num_traits::FromPrimitive;
+
+        for (bytes, expected) in [
+            ([0x06, 0x1e, 0x01, 0xb4], ErrorMessages::AlreadyReversed),
+            ([0x06, 0x1e, 0x01, 0xb5], ErrorMessages::ReversalNotPossible),
+        ] {
+            let AuthorizationResponse::Abort(packet) =
+                AuthorizationResponse::zvt_parse(&bytes).unwrap()
+            else {
+                panic!("Expected an abort");
+            };
+            assert_eq!(ErrorMessages::from_u8(packet.error), Some(expected));
+        }
+    }
+
+    #[tokio::test]
+    async fn test_abortable_stream() {
+        let (ecr, mut pt) = tokio::io::duplex(64);
+        let pt = tokio::spawn(async move {
+            let mut buf = [0; 64];
+            // The authorization.
+            pt.read_exact(&mut buf[..3]).await.unwrap();
+            assert_eq!(&buf[..2], &[0x06, 0x01]);
+            let len = buf[2] as usize;
+            pt.read_exact(&mut buf[..len]).await.unwrap();
+            pt.write_all(&[0x80, 0x00, 0x00]).await.unwrap();
+
+            // Waiting for the card.
+            pt.write_all(&[0x04, 0xff, 0x01, 0x0a]).await.unwrap();
+            pt.read_exact(&mut buf[..3]).await.unwrap();
+            assert_eq!(&buf[..3], &[0x80, 0x00, 0x00]);
+
+            // The abort.
+            pt.read_exact(&mut buf[..3]).await.unwrap();
+            assert_eq!(&buf[..3], &[0x06, 0xb0, 0x00]);
+            pt.write_all(&[0x80, 0x00, 0x00]).await.unwrap();
+            pt.write_all(&[0x06, 0x1e, 0x01, 0x6c]).await.unwrap();
+            pt.read_exact(&mut buf[..3]).await.unwrap();
+            assert_eq!(&buf[..3], &[0x80, 0x00, 0x00]);
+        });
+
+        let mut src = PacketTransport::new(ecr);
+        let input = packets::Authorization {
+            amount: Some(100),
+            ..packets::Authorization::default()
+        };
+        let (handle, mut stream) = Authorization::into_abortable_stream(&input, &mut src);
+
+        let packet = stream.next().await.unwrap().unwrap();
+        assert!(matches!(
+            packet,
+            AuthorizationResponse::IntermediateStatusInformation(_)
+        ));
+        handle.abort();
+
+        let packet = stream.next().await.unwrap().unwrap();
+        let AuthorizationResponse::Abort(packet) = packet else {
+            panic!("Expected an abort");
+        };
+        assert_eq!(packet.error, 0x6c);
+        assert!(stream.next().await.is_none());
+        drop(stream);
+        pt.await.unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_abortable_stream_nack() {
+        let (ecr, mut pt) = tokio::io::duplex(64);
+        let pt = tokio::spawn(async move {
+            let mut buf = [0; 64];
+            pt.read_exact(&mut buf[..3]).await.unwrap();
+            assert_eq!(&buf[..2], &[0x06, 0x01]);
+            let len = buf[2] as usize;
+            pt.read_exact(&mut buf[..len]).await.unwrap();
+            pt.write_all(&[0x80, 0x00, 0x00]).await.unwrap();
+
+            // The PT rejects the abort and completes the payment.
+            pt.read_exact(&mut buf[..3]).await.unwrap();
+            assert_eq!(&buf[..3], &[0x06, 0xb0, 0x00]);
+            pt.write_all(&[0x84, 0x9c, 0x00]).await.unwrap();
+            pt.write_all(&[0x06, 0x0f, 0x00]).await.unwrap();
+            pt.read_exact(&mut buf[..3]).await.unwrap();
+            assert_eq!(&buf[..3], &[0x80, 0x00, 0x00]);
+        });
+
+        let mut src = PacketTransport::new(ecr);
+        let input = packets::Authorization {
+            amount: Some(100),
+            ..packets::Authorization::default()
+        };
+        let (handle, mut stream) = Authorization::into_abortable_stream(&input, &mut src);
+        handle.abort();
+
+        assert!(matches!(
+            stream.next().await.unwrap().unwrap(),
+            AuthorizationResponse::CompletionData(_)
+        ));
+        assert!(stream.next().await.is_none());
+        drop(stream);
+        pt.await.unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_refund_stream() {
+        let (ecr, mut pt) = tokio::io::duplex(64);
+        let pt = tokio::spawn(async move {
+            let mut buf = [0; 64];
+            pt.read_exact(&mut buf[..3]).await.unwrap();
+            assert_eq!(&buf[..2], &[0x06, 0x31]);
+            let len = buf[2] as usize;
+            pt.read_exact(&mut buf[..len]).await.unwrap();
+            pt.write_all(&[0x80, 0x00, 0x00]).await.unwrap();
+
+            for packet in [&[0x04, 0xff, 0x01, 0x0a][..], &[0x06, 0x0f, 0x00]] {
+                pt.write_all(packet).await.unwrap();
+                pt.read_exact(&mut buf[..3]).await.unwrap();
+                assert_eq!(&buf[..3], &[0x80, 0x00, 0x00]);
             }
+        });
+
+        let mut src = PacketTransport::new(ecr);
+        let input = packets::Refund {
+            amount: Some(100),
+            ..packets::Refund::default()
         };
-        Box::pin(s)
+        let mut stream = Refund::into_stream(&input, &mut src);
+        assert!(matches!(
+            stream.next().await.unwrap().unwrap(),
+            AuthorizationResponse::IntermediateStatusInformation(_)
+        ));
+        assert!(matches!(
+            stream.next().await.unwrap().unwrap(),
+            AuthorizationResponse::CompletionData(_)
+        ));
+        assert!(stream.next().await.is_none());
+        drop(stream);
+        pt.await.unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_log_off() {
+        let (ecr, mut pt) = tokio::io::duplex(64);
+        let pt = tokio::spawn(async move {
+            let mut buf = [0; 3];
+            pt.read_exact(&mut buf).await.unwrap();
+            assert_eq!(buf, [0x06, 0x02, 0x00]);
+            pt.write_all(&[0x80, 0x00, 0x00]).await.unwrap();
+        });
+
+        let mut src = PacketTransport::new(ecr);
+        let mut stream = LogOff::into_stream(&packets::LogOff {}, &mut src);
+        assert!(matches!(
+            stream.next().await.unwrap().unwrap(),
+            LogOffResponse::Ack(_)
+        ));
+        assert!(stream.next().await.is_none());
+        pt.await.unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_abortable_stream_before_ack() {
+        let (ecr, mut pt) = tokio::io::duplex(64);
+        let pt = tokio::spawn(async move {
+            let mut buf = [0; 64];
+            // The authorization, acknowledged after the ECR requested the
+            // abort.
+            pt.read_exact(&mut buf[..3]).await.unwrap();
+            assert_eq!(&buf[..2], &[0x06, 0x01]);
+            let len = buf[2] as usize;
+            pt.read_exact(&mut buf[..len]).await.unwrap();
+            tokio::time::sleep(std::time::Duration::from_millis(20)).await;
+            pt.write_all(&[0x80, 0x00, 0x00]).await.unwrap();
+
+            // The abort must not be sent before the ACK.
+            pt.read_exact(&mut buf[..3]).await.unwrap();
+            assert_eq!(&buf[..3], &[0x06, 0xb0, 0x00]);
+
+            // A status sent before the PT processed the abort.
+            pt.write_all(&[0x04, 0xff, 0x01, 0x0a]).await.unwrap();
+            pt.read_exact(&mut buf[..3]).await.unwrap();
+            assert_eq!(&buf[..3], &[0x80, 0x00, 0x00]);
+
+            pt.write_all(&[0x80, 0x00, 0x00]).await.unwrap();
+            pt.write_all(&[0x06, 0x1e, 0x01, 0x6c]).await.unwrap();
+            pt.read_exact(&mut buf[..3]).await.unwrap();
+            assert_eq!(&buf[..3], &[0x80, 0x00, 0x00]);
+        });
+
+        let mut src = PacketTransport::new(ecr);
+        let input = packets::Authorization {
+            amount: Some(100),
+            ..packets::Authorization::default()
+        };
+        let (handle, mut stream) = Authorization::into_abortable_stream(&input, &mut src);
+        handle.abort();
+
+        let packet = stream.next().await.unwrap().unwrap();
+        assert!(matches!(
+            packet,
+            AuthorizationResponse::IntermediateStatusInformation(_)
+        ));
+        let packet = stream.next().await.unwrap().unwrap();
+        let AuthorizationResponse::Abort(packet) = packet else {
+            panic!("Expected an abort");
+        };
+        assert_eq!(packet.error, 0x6c);
+        assert!(stream.next().await.is_none());
+        drop(stream);
+        pt.await.unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_blocked_list_query() {
+        let (ecr, mut pt) = tokio::io::duplex(64);
+        let pt = tokio::spawn(async move {
+            let mut buf = [0; 3];
+            pt.read_exact(&mut buf).await.unwrap();
+            assert_eq!(buf, [0x06, 0xe4, 0x00]);
+            pt.write_all(&[0x80, 0x00, 0x00]).await.unwrap();
+            pt.write_all(&[0x06, 0x1e, 0x01, 0x6e]).await.unwrap();
+            pt.read_exact(&mut buf).await.unwrap();
+            assert_eq!(buf, [0x80, 0x00, 0x00]);
+        });
+
+        let mut src = PacketTransport::new(ecr);
+        let status = BlockedListQuery::query(&packets::BlockedListQuery::default(), &mut src)
+            .await
+            .unwrap();
+        assert!(status.blocked);
+        assert!(status.status_information.is_none());
+        pt.await.unwrap();
     }
 }
@@ -24,6 +24,9 @@ pub enum ZVTError {
 
     #[error("Received an abort {0}")]
     Aborted(u8),
+
+    #[error("Timeout while waiting for the PT")]
+    Timeout,
 }
 
 pub type ZVTResult<T> = ::std::result::Result<T, ZVTError>;
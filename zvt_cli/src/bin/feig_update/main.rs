@@ -55,7 +55,7 @@ async fn main() -> Result<()> {
 
     // Connect to the payment terminal.
     let source = TcpStream::connect(&args.ip_address).await?;
-    let mut socket = io::PacketTransport { source };
+    let mut socket = io::PacketTransport::new(source);
     const MAX_LEN_ADPU: u16 = 1u16 << 15;
     let registration = packets::Registration {
         password: args.password,
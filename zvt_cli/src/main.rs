@@ -27,6 +27,13 @@ enum SubCommands {
     Reservation(ReservationArgs),
     PartialReversal(PartialReversalArgs),
     ChangeHostConfiguration(ChangeHostConfigurationArgs),
+    ChangePassword(ChangePasswordArgs),
+    ChangeDisplayConfiguration(ChangeDisplayConfigurationArgs),
+    ChangeVolumeConfiguration(ChangeVolumeConfigurationArgs),
+    ChangeLanguageConfiguration(ChangeLanguageConfigurationArgs),
+    ChangeContactlessConfiguration(ChangeContactlessConfigurationArgs),
+    ChangeNetworkConfiguration(ChangeNetworkConfigurationArgs),
+    ReadFile(ReadFileArgs),
 }
 
 #[derive(Debug, PartialEq)]
@@ -251,6 +258,97 @@ struct ChangeHostConfigurationArgs {
     configuration_byte: u8,
 }
 
+#[derive(FromArgs, PartialEq, Debug)]
+/// Changes the display settings of the terminal.
+#[argh(subcommand, name = "change_display_config")]
+struct ChangeDisplayConfigurationArgs {
+    /// the brightness in percent.
+    #[argh(option)]
+    brightness: u8,
+
+    /// the time in seconds until the backlight is dimmed. Defaults to 30.
+    #[argh(option, default = "30")]
+    backlight_timeout: u16,
+}
+
+#[derive(FromArgs, PartialEq, Debug)]
+/// Changes the buzzer volume of the terminal.
+#[argh(subcommand, name = "change_volume_config")]
+struct ChangeVolumeConfigurationArgs {
+    /// the volume in percent.
+    #[argh(option)]
+    volume: u8,
+}
+
+#[derive(FromArgs, PartialEq, Debug)]
+/// Changes the default language of the terminal.
+#[argh(subcommand, name = "change_language_config")]
+struct ChangeLanguageConfigurationArgs {
+    /// the language as ISO 639-1 code, e.x. de or en.
+    #[argh(option)]
+    language: String,
+}
+
+#[derive(FromArgs, PartialEq, Debug)]
+/// Changes the contactless settings of the terminal.
+#[argh(subcommand, name = "change_contactless_config")]
+struct ChangeContactlessConfigurationArgs {
+    /// disables the contactless reader.
+    #[argh(switch)]
+    disable: bool,
+
+    /// the amount (in cents) up to which no cardholder verification is required. Defaults to 5000.
+    #[argh(option, default = "5000")]
+    cvm_limit: usize,
+}
+
+#[derive(FromArgs, PartialEq, Debug)]
+/// Changes the network settings of the terminal.
+#[argh(subcommand, name = "change_network_config")]
+struct ChangeNetworkConfigurationArgs {
+    /// use DHCP. If set, the remaining options are ignored.
+    #[argh(switch)]
+    dhcp: bool,
+
+    /// the static IP of the terminal.
+    #[argh(option, default = "Ipv4Addr::UNSPECIFIED")]
+    ip: Ipv4Addr,
+
+    /// the netmask.
+    #[argh(option, default = "Ipv4Addr::new(255, 255, 255, 0)")]
+    netmask: Ipv4Addr,
+
+    /// the gateway.
+    #[argh(option, default = "Ipv4Addr::UNSPECIFIED")]
+    gateway: Ipv4Addr,
+
+    /// the DNS server. Defaults to the gateway.
+    #[argh(option)]
+    dns: Option<Ipv4Addr>,
+}
+
+#[derive(FromArgs, PartialEq, Debug)]
+/// Changes the password of the payment terminal. The old password is given by --password.
+#[argh(subcommand, name = "change_password")]
+struct ChangePasswordArgs {
+    /// the new password.
+    #[argh(option)]
+    new_password: usize,
+}
+
+#[derive(FromArgs, PartialEq, Debug)]
+/// Reads a file from the terminal.
+#[argh(subcommand, name = "read_file")]
+struct ReadFileArgs {
+    /// the id of the file as defined in the cVEND manual, e.x. 0x13 (= 19) for firmware/update.spec.
+    #[argh(option)]
+    file_id: u8,
+
+    /// the local path to write the file to.
+    #[argh(option)]
+    output: String,
+}
+
 #[derive(FromArgs, Debug)]
 /// Example tool to interact with the payment terminal.
 struct Args {
@@ -367,6 +465,7 @@ async fn registration(
         use sequences::RegistrationResponse::*;
         match response? {
             CompletionData(data) => log::info!("{data:#?}"),
+            Abort(data) => log::info!("{data:#?}"),
         }
     }
     Ok(())
@@ -610,21 +709,23 @@ async fn change_host_config(
     password: usize,
     args: ChangeHostConfigurationArgs,
 ) -> Result<()> {
-    let request = feig::packets::ChangeConfiguration {
-        tlv: feig::packets::tlv::ChangeConfiguration {
-            system_information: feig::packets::tlv::SystemInformation {
-                password,
-                host_configuration_data: Some(feig::packets::tlv::HostConfigurationData {
-                    ip: args.ip.into(),
-                    port: args.port,
-                    config_byte: args.configuration_byte,
-                }),
-            },
+    let request = feig::packets::ChangeConfiguration::host(
+        password,
+        feig::packets::tlv::HostConfigurationData {
+            ip: args.ip.into(),
+            port: args.port,
+            config_byte: args.configuration_byte,
         },
-    };
+    );
+    change_config(socket, request).await
+}
 
-    let mut stream = feig::sequences::ChangeHostConfiguration::into_stream(&request, socket);
-    use feig::sequences::ChangeHostConfigurationResponse::*;
+async fn change_config(
+    socket: &mut PacketTransport,
+    request: feig::packets::ChangeConfiguration,
+) -> Result<()> {
+    let mut stream = feig::sequences::ChangeConfiguration::into_stream(&request, socket);
+    use feig::sequences::ChangeConfigurationResponse::*;
     while let Some(response) = stream.next().await {
         match response? {
             CompletionData(_) => (),
@@ -634,6 +735,136 @@ async fn change_host_config(
     Ok(())
 }
 
+async fn change_display_config(
+    socket: &mut PacketTransport,
+    password: usize,
+    args: ChangeDisplayConfigurationArgs,
+) -> Result<()> {
+    let request = feig::packets::ChangeConfiguration::display(
+        password,
+        feig::packets::tlv::DisplayConfigurationData {
+            brightness: args.brightness,
+            backlight_timeout: args.backlight_timeout,
+        },
+    );
+    change_config(socket, request).await
+}
+
+async fn change_volume_config(
+    socket: &mut PacketTransport,
+    password: usize,
+    args: ChangeVolumeConfigurationArgs,
+) -> Result<()> {
+    let request = feig::packets::ChangeConfiguration::volume(
+        password,
+        feig::packets::tlv::VolumeConfigurationData {
+            volume: args.volume,
+        },
+    );
+    change_config(socket, request).await
+}
+
+async fn change_language_config(
+    socket: &mut PacketTransport,
+    password: usize,
+    args: ChangeLanguageConfigurationArgs,
+) -> Result<()> {
+    if args.language.len() != 2 {
+        bail!("The language must be a two letter code.");
+    }
+    let request = feig::packets::ChangeConfiguration::language(
+        password,
+        feig::packets::tlv::LanguageConfigurationData {
+            language: args.language.to_lowercase(),
+        },
+    );
+    change_config(socket, request).await
+}
+
+async fn change_contactless_config(
+    socket: &mut PacketTransport,
+    password: usize,
+    args: ChangeContactlessConfigurationArgs,
+) -> Result<()> {
+    let request = feig::packets::ChangeConfiguration::contactless(
+        password,
+        feig::packets::tlv::ContactlessConfigurationData {
+            enabled: (!args.disable).into(),
+            cvm_limit: args.cvm_limit,
+        },
+    );
+    change_config(socket, request).await
+}
+
+async fn change_network_config(
+    socket: &mut PacketTransport,
+    password: usize,
+    args: ChangeNetworkConfigurationArgs,
+) -> Result<()> {
+    if !args.dhcp && args.ip.is_unspecified() {
+        bail!("Either --dhcp or --ip must be given.");
+    }
+    let request = feig::packets::ChangeConfiguration::network(
+        password,
+        feig::packets::tlv::NetworkConfigurationData {
+            dhcp: args.dhcp.into(),
+            ip: args.ip.into(),
+            netmask: args.netmask.into(),
+            gateway: args.gateway.into(),
+            dns: args.dns.unwrap_or(args.gateway).into(),
+        },
+    );
+    change_config(socket, request).await
+}
+
+async fn change_password(
+    socket: &mut PacketTransport,
+    password: usize,
+    args: &ChangePasswordArgs,
+) -> Result<()> {
+    let request = packets::ChangePassword {
+        old_password: password,
+        new_password: args.new_password,
+    };
+
+    let mut stream = sequences::ChangePassword::into_stream(&request, socket);
+    while let Some(response) = stream.next().await {
+        use sequences::ChangePasswordResponse::*;
+        match response? {
+            CompletionData(data) => log::info!("{data:#?}"),
+            Abort(data) => bail!("Received Abort: {:?}", data),
+        }
+    }
+    Ok(())
+}
+
+async fn read_file(socket: &mut PacketTransport, password: usize, args: ReadFileArgs) -> Result<()> {
+    let request = feig::packets::ReadFileParameter {
+        path: args.output,
+        password,
+        file_id: args.file_id,
+    };
+
+    let mut stream = feig::sequences::ReadFile::into_stream(&request, socket);
+    use feig::sequences::ReadFileResponse::*;
+    while let Some(response) = stream.next().await {
+        match response? {
+            ReadFileCompletion(data) => {
+                if let Some(file) = data.tlv.and_then(|tlv| tlv.file) {
+                    log::info!(
+                        "Read {} bytes at offset {:?} of {:?}",
+                        file.payload.map_or(0, |p| p.len()),
+                        file.file_offset,
+                        file.file_size
+                    );
+                }
+            }
+            Abort(data) => bail!("Received Abort: {:?}", data),
+        }
+    }
+    Ok(())
+}
+
 #[tokio::main]
 async fn main() -> Result<()> {
     init_logger();
@@ -641,7 +872,7 @@ async fn main() -> Result<()> {
 
     let mut socket = {
         let source = TcpStream::connect(args.ip).await?;
-        PacketTransport { source }
+        PacketTransport::new(source)
     };
 
     match args.command {
@@ -662,6 +893,23 @@ async fn main() -> Result<()> {
         SubCommands::ChangeHostConfiguration(a) => {
             change_host_config(&mut socket, args.password, a).await?
         }
+        SubCommands::ChangePassword(a) => change_password(&mut socket, args.password, &a).await?,
+        SubCommands::ChangeDisplayConfiguration(a) => {
+            change_display_config(&mut socket, args.password, a).await?
+        }
+        SubCommands::ChangeVolumeConfiguration(a) => {
+            change_volume_config(&mut socket, args.password, a).await?
+        }
+        SubCommands::ChangeLanguageConfiguration(a) => {
+            change_language_config(&mut socket, args.password, a).await?
+        }
+        SubCommands::ChangeContactlessConfiguration(a) => {
+            change_contactless_config(&mut socket, args.password, a).await?
+        }
+        SubCommands::ChangeNetworkConfiguration(a) => {
+            change_network_config(&mut socket, args.password, a).await?
+        }
+        SubCommands::ReadFile(a) => read_file(&mut socket, args.password, a).await?,
     }
 
     Ok(())
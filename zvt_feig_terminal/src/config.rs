@@ -32,6 +32,22 @@ pub struct FeigConfig {
     /// Maximum number of retry attempts for network operations.
     #[serde(default = "max_retry_attempts")]
     pub max_retry_attempts: usize,
+
+    /// Commits transactions with Book Total (06 24) instead of Partial
+    /// Reversal (06 23). Some acquirers only accept the former.
+    #[serde(default)]
+    pub use_book_total: bool,
+
+    /// The new password to the payment terminal. The password is changed
+    /// from [Self::password] to this one by
+    /// [crate::feig::Feig::change_password]. If the terminal rejects
+    /// [Self::password] during the registration, we retry with this one.
+    #[serde(default)]
+    pub new_password: Option<usize>,
+
+    /// Uploads the stored offline transactions before updating the firmware.
+    #[serde(default)]
+    pub send_offline_transactions_before_update: bool,
 }
 
 /// Deserializer which consumes a string code and returns the numerical code.
@@ -71,6 +87,9 @@ impl Default for FeigConfig {
             password: 0,
             end_of_day_max_interval: end_of_day_max_interval(),
             max_retry_attempts: max_retry_attempts(),
+            use_book_total: false,
+            new_password: None,
+            send_offline_transactions_before_update: false,
         }
     }
 }
@@ -137,6 +156,16 @@ mod tests {
         .unwrap();
         assert_eq!(with_all.currency, 826);
         assert_eq!(with_all.end_of_day_max_interval, 1234);
+        assert!(!with_all.use_book_total);
+        assert_eq!(with_all.new_password, None);
+
+        let with_book_total =
+            serde_json::from_str::<FeigConfig>("{\"use_book_total\": true}").unwrap();
+        assert!(with_book_total.use_book_total);
+
+        let with_new_password =
+            serde_json::from_str::<FeigConfig>("{\"new_password\": 654321}").unwrap();
+        assert_eq!(with_new_password.new_password, Some(654321));
 
         // Invalid inputs.
         assert!(serde_json::from_str::<FeigConfig>("{\"currency\": \"ABC\"}").is_err());
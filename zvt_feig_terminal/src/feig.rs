@@ -123,6 +123,12 @@ struct TransactionData {
 
     /// The pre-auth amount of the transaction.
     pre_authorization_amount: usize,
+
+    /// The trace number of the transaction. Required for [sequences::BookTotal].
+    trace_number: Option<usize>,
+
+    /// The AID of the transaction. Required for [sequences::BookTotal].
+    aid_authorization_attribute: Option<String>,
 }
 
 pub struct Feig {
@@ -161,17 +167,30 @@ impl Feig {
     }
 
     /// Reconnects under the given ip-address.
+    ///
+    /// Logs off from the currently connected terminal first.
     pub async fn reconnect(&mut self, ip_address: Ipv4Addr) -> Result<()> {
         let config = {
             let mut config = self.socket.config().clone();
             config.ip_address = ip_address;
             config
         };
+        if let Err(err) = self.socket.log_off().await {
+            warn!("Failed to log off: {err:?}");
+        }
         self.socket = TcpStream::new(config)?;
         // This checks if the new connection is sound.
         self.configure().await
     }
 
+    /// Logs off from the terminal.
+    ///
+    /// Call this before shutting down the ECR, so the terminal is not left in
+    /// a registered state. Any later call will reconnect to the terminal.
+    pub async fn log_off(&mut self) -> Result<()> {
+        self.socket.log_off().await
+    }
+
     /// Returns the system information of the feig-terminal.
     async fn get_system_info(
         &mut self,
@@ -420,6 +439,78 @@ impl Feig {
         Err(error)
     }
 
+    /// Changes the password of the PT to [crate::config::FeigConfig::new_password].
+    ///
+    /// The rotation is a one-shot operation and not part of [Feig::configure].
+    /// On success all further requests use the new password. Since the config
+    /// still contains the old password after a restart, the registration falls
+    /// back to the new password if the PT rejects the old one.
+    ///
+    /// Does nothing if the password was already changed and fails if no new
+    /// password is configured or if the PT rejects the change.
+    pub async fn change_password(&mut self) -> Result<()> {
+        let feig_config = &self.socket.config().feig_config;
+        let Some(new_password) = feig_config.new_password else {
+            bail!("No new password configured");
+        };
+        if new_password == feig_config.password {
+            return Ok(());
+        }
+        let request = packets::ChangePassword {
+            old_password: feig_config.password,
+            new_password,
+        };
+
+        let mut error = zvt::ZVTError::IncompleteData.into();
+        let mut stream = sequences::ChangePassword::into_stream(request, &mut self.socket);
+        while let Some(response) = stream.next().await {
+            let response = match response {
+                Ok(response) => response,
+                Err(err) => {
+                    error = err;
+                    continue;
+                }
+            };
+            match response {
+                sequences::ChangePasswordResponse::CompletionData(_) => {
+                    drop(stream);
+                    info!("Changed the password of the terminal");
+                    self.socket.set_password(new_password);
+                    return Ok(());
+                }
+                sequences::ChangePasswordResponse::Abort(data) => {
+                    bail!(zvt::ZVTError::Aborted(data.error))
+                }
+            }
+        }
+        Err(error)
+    }
+
+    /// Uploads the transactions the PT stored while being offline.
+    async fn send_offline_transactions(&mut self) -> Result<()> {
+        let password = self.socket.config().feig_config.password;
+        let request = packets::SendOfflineTransactions { password };
+
+        let mut error = zvt::ZVTError::IncompleteData.into();
+        let mut stream = sequences::SendOfflineTransactions::into_stream(request, &mut self.socket);
+        while let Some(response) = stream.next().await {
+            let response = match response {
+                Ok(response) => response,
+                Err(err) => {
+                    error = err;
+                    continue;
+                }
+            };
+            use sequences::SendOfflineTransactionsResponse::*;
+            match response {
+                IntermediateStatusInformation(_) => (),
+                CompletionData(_) => return Ok(()),
+                Abort(data) => bail!(zvt::ZVTError::Aborted(data.error)),
+            }
+        }
+        Err(error)
+    }
+
     async fn status_enquiry(&mut self) -> Result<constants::TerminalStatusCode> {
         // Get the status inquiry so we can reason on the terminal_status_code.
         let password = self.socket.config().feig_config.password;
@@ -642,6 +733,8 @@ impl Feig {
 
         let mut error = zvt::ZVTError::IncompleteData.into();
         let mut receipt_no = None;
+        let mut trace_number = None;
+        let mut aid_authorization_attribute = None;
         let mut stream = sequences::Reservation::into_stream(request, &mut self.socket);
         while let Some(response) = stream.next().await {
             let response = match response {
@@ -664,6 +757,12 @@ impl Feig {
                     if let Some(inner) = data.receipt_no {
                         receipt_no = Some(inner);
                     }
+                    if let Some(inner) = data.trace_number {
+                        trace_number = Some(inner);
+                    }
+                    if let Some(inner) = data.aid_authorization_attribute {
+                        aid_authorization_attribute = Some(inner);
+                    }
                 }
                 _ => {}
             }
@@ -675,6 +774,8 @@ impl Feig {
                 let transaction_data = TransactionData {
                     receipt_no,
                     pre_authorization_amount,
+                    trace_number,
+                    aid_authorization_attribute,
                 };
                 self.transactions
                     .insert(token.to_string(), transaction_data);
@@ -744,6 +845,10 @@ impl Feig {
     /// argument must contain a [Transaction::token] matching the token from
     /// [Feig::begin_transaction]. The method fails if the `token` is unknown.
     ///
+    /// Under the hood the method maps to [sequences::PartialReversal] or, if
+    /// [crate::config::FeigConfig::use_book_total] is set, to
+    /// [sequences::BookTotal].
+    ///
     /// # Arguments
     /// * `token` - The token under which the transaction is known.
     /// * `amount` - The amount in fractional monetary unit.
@@ -761,25 +866,40 @@ impl Feig {
         };
 
         let config = self.socket.config();
-        let reversal_amount = transaction
-            .pre_authorization_amount
-            .saturating_sub(amount as usize);
-
-        let request = packets::PartialReversal {
-            receipt_no: Some(transaction.receipt_no),
-            currency: Some(config.feig_config.currency),
-            amount: Some(reversal_amount),
-            payment_type: PAYMENT_TYPE,
-            tlv: Some(packets::tlv::PreAuthData {
-                bmp_data: Some(packets::tlv::Bmp60 {
-                    bmp_prefix: BMP_PREFIX.to_string(),
-                    bmp_data: token.to_string(),
-                }),
+        let tlv = Some(packets::tlv::PreAuthData {
+            bmp_data: Some(packets::tlv::Bmp60 {
+                bmp_prefix: BMP_PREFIX.to_string(),
+                bmp_data: token.to_string(),
             }),
+        });
+
+        let mut stream = if config.feig_config.use_book_total {
+            let request = packets::BookTotal {
+                receipt_no: Some(transaction.receipt_no),
+                amount: Some(amount as usize),
+                currency: Some(config.feig_config.currency),
+                payment_type: PAYMENT_TYPE,
+                trace_number: transaction.trace_number,
+                aid_authorization_attribute: transaction.aid_authorization_attribute.clone(),
+                tlv,
+            };
+            sequences::BookTotal::into_stream(request, &mut self.socket)
+        } else {
+            let reversal_amount = transaction
+                .pre_authorization_amount
+                .saturating_sub(amount as usize);
+
+            let request = packets::PartialReversal {
+                receipt_no: Some(transaction.receipt_no),
+                currency: Some(config.feig_config.currency),
+                amount: Some(reversal_amount),
+                payment_type: PAYMENT_TYPE,
+                tlv,
+            };
+            sequences::PartialReversal::into_stream(request, &mut self.socket)
         };
 
         let mut error = zvt::ZVTError::IncompleteData.into();
-        let mut stream = sequences::PartialReversal::into_stream(request, &mut self.socket);
         let mut status_information = None;
         while let Some(response) = stream.next().await {
             use sequences::PartialReversalResponse::*;
@@ -857,6 +977,14 @@ impl Feig {
             }
         }
 
+        if self
+            .socket
+            .config()
+            .feig_config
+            .send_offline_transactions_before_update
+        {
+            self.send_offline_transactions().await?;
+        }
         self.end_of_day().await?;
 
         // Update the firmware
@@ -884,3 +1012,134 @@ impl Feig {
         Ok(())
     }
 }
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::config::FeigConfig;
+    use crate::stream::test::fake_pt;
+    use zvt::ZvtSerializer;
+
+    /// Returns a [Feig] which is connected to the returned fake PT.
+    fn get_feig(feig_config: FeigConfig) -> (Feig, tokio::io::DuplexStream) {
+        let (ecr, pt) = tokio::io::duplex(1024);
+        let config = Config {
+            feig_config,
+            ..Config::default()
+        };
+        let feig = Feig {
+            socket: TcpStream::with_duplex(config, ecr),
+            transactions: HashMap::new(),
+            transactions_max_num: 1,
+            end_of_day_max_interval: Duration::from_secs(1000),
+            end_of_day_last_instant: std::time::Instant::now(),
+        };
+        (feig, pt)
+    }
+
+    fn get_feig_config() -> FeigConfig {
+        FeigConfig {
+            password: 123456,
+            new_password: Some(654321),
+            ..FeigConfig::default()
+        }
+    }
+
+    #[tokio::test]
+    async fn test_change_password() {
+        let (mut feig, mut pt) = get_feig(get_feig_config());
+
+        let pt_task = tokio::spawn(async move {
+            let request = fake_pt(&mut pt, &[&[0x06, 0x0f, 0x00]]).await;
+            assert_eq!(
+                request,
+                [0x06, 0x95, 0x06, 0x12, 0x34, 0x56, 0x65, 0x43, 0x21]
+            );
+        });
+
+        feig.change_password().await.unwrap();
+        pt_task.await.unwrap();
+        assert_eq!(feig.socket.config().feig_config.password, 654321);
+
+        // The password is already changed - nothing to do.
+        feig.change_password().await.unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_change_password_abort() {
+        let (mut feig, mut pt) = get_feig(get_feig_config());
+
+        let pt_task = tokio::spawn(async move {
+            fake_pt(&mut pt, &[&[0x06, 0x1e, 0x01, 0x83]]).await;
+        });
+
+        let err = feig.change_password().await.unwrap_err();
+        assert_eq!(
+            err.downcast::<zvt::ZVTError>().unwrap(),
+            zvt::ZVTError::Aborted(0x83)
+        );
+        pt_task.await.unwrap();
+        assert_eq!(feig.socket.config().feig_config.password, 123456);
+    }
+
+    #[tokio::test]
+    async fn test_change_password_not_configured() {
+        let (mut feig, _pt) = get_feig(FeigConfig {
+            new_password: None,
+            ..get_feig_config()
+        });
+
+        assert!(feig.change_password().await.is_err());
+    }
+
+    #[tokio::test]
+    async fn test_commit_transaction_book_total() {
+        let (mut feig, mut pt) = get_feig(FeigConfig {
+            use_book_total: true,
+            ..get_feig_config()
+        });
+        feig.transactions.insert(
+            "token".to_string(),
+            TransactionData {
+                receipt_no: 231,
+                pre_authorization_amount: 5000,
+                trace_number: Some(975),
+                aid_authorization_attribute: Some("750071".to_string()),
+            },
+        );
+
+        let status_information = packets::StatusInformation {
+            amount: Some(2500),
+            trace_number: Some(976),
+            terminal_id: Some(52523535),
+            ..packets::StatusInformation::default()
+        };
+        let status_information = status_information.zvt_serialize();
+        let pt_task = tokio::spawn(async move {
+            let request = fake_pt(&mut pt, &[&status_information, &[0x06, 0x0f, 0x00]]).await;
+            packets::BookTotal::zvt_deserialize(&request).unwrap().0
+        });
+
+        let summary = feig.commit_transaction("token", 2500).await.unwrap();
+        assert_eq!(summary.amount, Some(2500));
+        assert_eq!(summary.trace_number, Some(976));
+        assert_eq!(summary.terminal_id.as_deref(), Some("52523535"));
+        assert!(feig.transactions.is_empty());
+
+        let expected = packets::BookTotal {
+            receipt_no: Some(231),
+            amount: Some(2500),
+            currency: Some(978),
+            payment_type: PAYMENT_TYPE,
+            trace_number: Some(975),
+            aid_authorization_attribute: Some("750071".to_string()),
+            tlv: Some(packets::tlv::PreAuthData {
+                bmp_data: Some(packets::tlv::Bmp60 {
+                    bmp_prefix: BMP_PREFIX.to_string(),
+                    bmp_data: "token".to_string(),
+                }),
+            }),
+        };
+        assert_eq!(pt_task.await.unwrap(), expected);
+    }
+}
@@ -13,11 +13,61 @@ use futures::stream::BoxStream;
 use log::{debug, info, warn};
 use std::net::{SocketAddr, SocketAddrV4};
 use std::time::Duration;
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
 use tokio_stream::StreamExt;
 use zvt::{encoding, feig, io, packets, sequences, sequences::Sequence};
 
 const TIMEOUT: Duration = Duration::from_secs(60);
 
+/// Configuration byte used for the registration.
+const CONFIG_BYTE: u8 = 0xde;
+
+/// Registers to the terminal.
+///
+/// Uses [crate::config::FeigConfig::password] first. If the PT rejects it and
+/// a [crate::config::FeigConfig::new_password] is configured, e.x. since the
+/// password was rotated before a restart, we retry with the new password and
+/// keep it in the `config` for all further requests.
+async fn register<S>(socket: &mut io::PacketTransport<S>, config: &mut Config) -> Result<()>
+where
+    S: AsyncReadExt + AsyncWriteExt + Unpin + Send,
+{
+    let feig_config = &config.feig_config;
+    let mut passwords = vec![feig_config.password];
+    if let Some(new_password) = feig_config.new_password {
+        if new_password != feig_config.password {
+            passwords.push(new_password);
+        }
+    }
+
+    let mut error = zvt::ZVTError::IncompleteData.into();
+    for password in passwords {
+        let request = packets::Registration {
+            password,
+            config_byte: CONFIG_BYTE,
+            currency: Some(config.feig_config.currency),
+            tlv: None,
+        };
+
+        let mut stream = <sequences::Registration as Sequence>::into_stream(&request, socket);
+        match stream.next().await {
+            Some(Ok(sequences::RegistrationResponse::CompletionData(completion))) => {
+                info!("Registered to the terminal {:?}", completion);
+                drop(stream);
+                config.feig_config.password = password;
+                return Ok(());
+            }
+            Some(Ok(sequences::RegistrationResponse::Abort(data))) => {
+                warn!("The terminal rejected the registration: {}", data.error);
+                error = zvt::ZVTError::Aborted(data.error).into();
+            }
+            Some(Err(err)) => return Err(err),
+            None => bail!(zvt::ZVTError::IncompleteData),
+        }
+    }
+    Err(error)
+}
+
 /// The implementation of our I/O.
 ///
 /// We're using a custom switch very similar to what [mockall_double::double]
@@ -39,13 +89,13 @@ mod outer {
         /// Reconnection
         ///
         /// Tries to open a new [InnerTcpStream] to the PT defined in the
-        /// `config` and performs basic registration to the terminal.
+        /// `config` and performs basic registration to the terminal. The
+        /// `config` is updated if the registration falls back to the new
+        /// password, see [register].
         ///
         /// We mock this function in the test configuration.
         #[cfg_attr(test, allow(dead_code))]
-        pub async fn connect(config: &Config) -> Result<io::PacketTransport<InnerTcpStream>> {
-            // Configuration byte.
-            pub const CONFIG_BYTE: u8 = 0xde;
+        pub async fn connect(config: &mut Config) -> Result<io::PacketTransport<InnerTcpStream>> {
             let address = SocketAddr::V4(SocketAddrV4::new(config.ip_address, 22000));
             #[cfg(not(test))]
             let source: InnerTcpStream = {
@@ -63,23 +113,10 @@ mod outer {
             #[cfg(test)]
             let source = InnerTcpStream::connect(address).await?;
 
-            let mut socket = io::PacketTransport { source };
-
-            let request = packets::Registration {
-                password: config.feig_config.password,
-                config_byte: CONFIG_BYTE,
-                currency: Some(config.feig_config.currency),
-                tlv: None,
-            };
+            let mut socket = io::PacketTransport::new(source);
 
             // Register to the terminal.
-            let mut stream =
-                <sequences::Registration as Sequence>::into_stream(&request, &mut socket);
-            while let Some(response) = stream.next().await {
-                let completion = response?;
-                info!("Registered to the terminal {:?}", completion);
-            }
-            drop(stream);
+            register(&mut socket, config).await?;
 
             // Verify that we're connected to the right terminal.
             let request = feig::packets::CVendFunctions {
@@ -142,6 +179,33 @@ impl TcpStream {
     pub fn config(&self) -> &Config {
         &self.config
     }
+
+    /// Updates the password used to (re-)register to the PT.
+    pub fn set_password(&mut self, password: usize) {
+        self.config.feig_config.password = password;
+    }
+
+    /// Logs off from the PT and drops the connection.
+    ///
+    /// Does nothing if there is no connection. The connection is dropped
+    /// regardless of the outcome of the [sequences::LogOff], so the next
+    /// sequence will reconnect (and register) again.
+    pub async fn log_off(&mut self) -> Result<()> {
+        let Some(mut inner) = self.inner.take() else {
+            return Ok(());
+        };
+
+        let request = packets::LogOff {};
+        let mut stream = <sequences::LogOff as Sequence>::into_stream(&request, &mut inner);
+        match tokio::time::timeout(TIMEOUT, stream.next()).await {
+            Ok(Some(response)) => {
+                info!("Logged off from the terminal {:?}", response?);
+                Ok(())
+            }
+            Ok(None) => bail!(zvt::ZVTError::IncompleteData),
+            Err(_) => bail!("Timeout while logging off"),
+        }
+    }
 }
 
 /// One of our most important.
@@ -190,7 +254,7 @@ where
                 // We don't have a valid connection - we must reconnect.
                 if src.inner.is_none() {
                     warn!("Reconnecting");
-                    match inner::connect(&src.config).await {
+                    match inner::connect(&mut src.config).await {
                         Ok(inner) => src.inner = Some(inner),
                         Err(err) => {
                             warn!("Failed to reconnect: {err:?}");
@@ -201,14 +265,22 @@ where
                 }
 
                 // Start the underlying stream.
+                let intermediate_timeout = src.inner.as_ref().unwrap().intermediate_timeout();
                 let mut stream =
                     <Self as Sequence>::into_stream(&input, src.inner.as_mut().unwrap());
                 let mut is_err = false;
                 // We are awaiting packets with a timeout. In case of a timeout
                 // we convert the error into None, to break out of the loop. The
                 // timeout is needed since we may not finish the preceding
-                // sequence and hang.
-                while let Some(packet) = match tokio::time::timeout(timeout, stream.next()).await {
+                // sequence and hang. If the PT announces a longer timeout in
+                // its intermediate status (e.x. during the PIN entry) we
+                // extend ours accordingly.
+                while let Some(packet) = match tokio::time::timeout(
+                    intermediate_timeout.get().map_or(timeout, |t| t.max(timeout)),
+                    stream.next(),
+                )
+                .await
+                {
                     Ok(packet) => packet,
                     Err(_) => {
                         warn!("Timeout");
@@ -245,7 +317,7 @@ where
 }
 
 #[cfg(test)]
-mod test {
+pub(crate) mod test {
     use super::*;
     use crate::config::FeigConfig;
     use std::pin::Pin;
@@ -254,7 +326,25 @@ mod test {
     use tokio::net::ToSocketAddrs;
 
     /// Mocked TcpStream.
-    pub struct MockTcpStream;
+    ///
+    /// Optionally backed by a [tokio::io::DuplexStream] for tests which talk
+    /// to a fake PT.
+    #[derive(Default)]
+    pub struct MockTcpStream {
+        duplex: Option<tokio::io::DuplexStream>,
+    }
+
+    impl TcpStream {
+        /// Creates a [TcpStream] which is connected to the given `duplex`.
+        pub(crate) fn with_duplex(config: Config, duplex: tokio::io::DuplexStream) -> Self {
+            Self {
+                config,
+                inner: Some(io::PacketTransport::new(MockTcpStream {
+                    duplex: Some(duplex),
+                })),
+            }
+        }
+    }
 
     impl MockTcpStream {
         /// Interface for connecting - the same signature as
@@ -275,10 +365,13 @@ mod test {
     impl AsyncRead for MockTcpStream {
         fn poll_read(
             self: Pin<&mut Self>,
-            _: &mut Context<'_>,
-            _: &mut tokio::io::ReadBuf<'_>,
+            cx: &mut Context<'_>,
+            buf: &mut tokio::io::ReadBuf<'_>,
         ) -> Poll<std::io::Result<()>> {
-            unimplemented!("just a mock")
+            match self.get_mut().duplex.as_mut() {
+                Some(duplex) => Pin::new(duplex).poll_read(cx, buf),
+                None => unimplemented!("just a mock"),
+            }
         }
     }
 
@@ -287,25 +380,54 @@ mod test {
     impl AsyncWrite for MockTcpStream {
         fn poll_write(
             self: Pin<&mut Self>,
-            _: &mut Context<'_>,
-            _: &[u8],
+            cx: &mut Context<'_>,
+            buf: &[u8],
         ) -> Poll<std::result::Result<usize, std::io::Error>> {
-            unimplemented!("just a mock")
+            match self.get_mut().duplex.as_mut() {
+                Some(duplex) => Pin::new(duplex).poll_write(cx, buf),
+                None => unimplemented!("just a mock"),
+            }
         }
 
         fn poll_flush(
             self: Pin<&mut Self>,
-            _: &mut Context<'_>,
+            cx: &mut Context<'_>,
         ) -> Poll<std::result::Result<(), std::io::Error>> {
-            unimplemented!("just a mock")
+            match self.get_mut().duplex.as_mut() {
+                Some(duplex) => Pin::new(duplex).poll_flush(cx),
+                None => unimplemented!("just a mock"),
+            }
         }
 
         fn poll_shutdown(
             self: Pin<&mut Self>,
-            _: &mut Context<'_>,
+            cx: &mut Context<'_>,
         ) -> Poll<std::result::Result<(), std::io::Error>> {
-            unimplemented!("just a mock")
+            match self.get_mut().duplex.as_mut() {
+                Some(duplex) => Pin::new(duplex).poll_shutdown(cx),
+                None => unimplemented!("just a mock"),
+            }
+        }
+    }
+
+    /// Fake PT which reads one request and acknowledges it.
+    ///
+    /// Afterwards it sends the `responses` and awaits the ECR's ACK for each.
+    /// Returns the request.
+    pub(crate) async fn fake_pt(pt: &mut tokio::io::DuplexStream, responses: &[&[u8]]) -> Vec<u8> {
+        let mut request = vec![0; 3];
+        pt.read_exact(&mut request).await.unwrap();
+        request.resize(3 + request[2] as usize, 0);
+        pt.read_exact(&mut request[3..]).await.unwrap();
+        pt.write_all(&[0x80, 0x00, 0x00]).await.unwrap();
+
+        for response in responses {
+            pt.write_all(response).await.unwrap();
+            let mut ack = [0; 3];
+            pt.read_exact(&mut ack).await.unwrap();
+            assert_eq!(ack, [0x80, 0x00, 0x00]);
         }
+        request
     }
 
     /// Fake sequence which always fails.
@@ -380,6 +502,34 @@ mod test {
         }
     }
 
+    /// Fake sequence which announces a long timeout before its slow message.
+    struct AnnouncingSequence {}
+
+    impl sequences::Sequence for AnnouncingSequence {
+        type Input = feig::packets::CVendFunctions;
+        type Output = feig::sequences::GetSystemInfoResponse;
+
+        /// Announcing stream
+        ///
+        /// Pretends that the PT announced a timeout of one minute and returns
+        /// a message after a short delay.
+        fn into_stream<'a, Source>(
+            _: &'a Self::Input,
+            src: &'a mut io::PacketTransport<Source>,
+        ) -> std::pin::Pin<Box<dyn futures::Stream<Item = Result<Self::Output>> + Send + 'a>>
+        where
+            Source: AsyncReadExt + AsyncWriteExt + Unpin + Send,
+            Self: 'a,
+        {
+            src.intermediate_timeout()
+                .set(Some(Duration::from_secs(60)));
+            Box::pin(futures::stream::once(async {
+                tokio::time::sleep(Duration::from_millis(50)).await;
+                Ok(Self::Output::Abort(zvt::packets::Abort { error: 0 }))
+            }))
+        }
+    }
+
     fn get_config() -> Config {
         Config {
             feig_config: FeigConfig {
@@ -388,6 +538,9 @@ mod test {
                 password: 123456,
                 end_of_day_max_interval: 1000,
                 max_retry_attempts: 20,
+                use_book_total: false,
+                new_password: None,
+                send_offline_transactions_before_update: false,
             },
             ..Config::default()
         }
@@ -420,11 +573,8 @@ mod test {
         assert!(socket.inner.is_none());
 
         // Now pretend that the connection was successful.
-        ctx.expect().returning(|_| {
-            Ok(io::PacketTransport {
-                source: InnerTcpStream {},
-            })
-        });
+        ctx.expect()
+            .returning(|_| Ok(io::PacketTransport::new(InnerTcpStream::default())));
         let repeater = futures::stream::repeat(()).take(1);
         let request = feig::packets::CVendFunctions {
             password: None,
@@ -457,11 +607,9 @@ mod test {
         // Now try with retrying the fail sequence.
         socket.inner = None;
         let attempts = 2;
-        ctx.expect().times(attempts).returning(|_| {
-            Ok(io::PacketTransport {
-                source: InnerTcpStream {},
-            })
-        });
+        ctx.expect()
+            .times(attempts)
+            .returning(|_| Ok(io::PacketTransport::new(InnerTcpStream::default())));
 
         let repeater = futures::stream::repeat(()).take(attempts);
         let request = feig::packets::CVendFunctions {
@@ -480,11 +628,8 @@ mod test {
         assert!(socket.inner.is_none());
 
         // Now try with a timeout.
-        ctx.expect().returning(|_| {
-            Ok(io::PacketTransport {
-                source: InnerTcpStream {},
-            })
-        });
+        ctx.expect()
+            .returning(|_| Ok(io::PacketTransport::new(InnerTcpStream::default())));
         let repeater = futures::stream::repeat(()).take(1);
         let request = feig::packets::CVendFunctions {
             password: None,
@@ -501,5 +646,97 @@ mod test {
         drop(stream);
         // The inner should still be none.
         assert!(socket.inner.is_none());
+
+        // Now the PT announces a timeout longer than ours.
+        ctx.expect()
+            .returning(|_| Ok(io::PacketTransport::new(InnerTcpStream::default())));
+        let repeater = futures::stream::repeat(()).take(1);
+        let request = feig::packets::CVendFunctions {
+            password: None,
+            instr: 0,
+        };
+        let mut stream = AnnouncingSequence::into_stream_with_retry(
+            request,
+            &mut socket,
+            repeater,
+            Duration::from_millis(10),
+        );
+        assert!(stream.next().await.unwrap().is_ok());
+        assert!(stream.next().await.is_none());
+        drop(stream);
+        assert!(socket.inner.is_some());
+    }
+
+    #[tokio::test]
+    async fn test_register_with_new_password() {
+        let (ecr, mut pt) = tokio::io::duplex(1024);
+        let mut socket = io::PacketTransport::new(ecr);
+        let mut config = get_config();
+        config.feig_config.new_password = Some(654321);
+
+        let pt_task = tokio::spawn(async move {
+            // The old password is rejected...
+            let request = fake_pt(&mut pt, &[&[0x06, 0x1e, 0x01, 0x83]]).await;
+            assert_eq!(request[..6], [0x06, 0x00, 0x06, 0x12, 0x34, 0x56]);
+            // ...the new one is accepted.
+            let request = fake_pt(&mut pt, &[&[0x06, 0x0f, 0x00]]).await;
+            assert_eq!(request[..6], [0x06, 0x00, 0x06, 0x65, 0x43, 0x21]);
+        });
+
+        register(&mut socket, &mut config).await.unwrap();
+        pt_task.await.unwrap();
+        assert_eq!(config.feig_config.password, 654321);
+    }
+
+    #[tokio::test]
+    async fn test_register_rejected() {
+        let (ecr, mut pt) = tokio::io::duplex(1024);
+        let mut socket = io::PacketTransport::new(ecr);
+        let mut config = get_config();
+
+        let pt_task = tokio::spawn(async move {
+            fake_pt(&mut pt, &[&[0x06, 0x1e, 0x01, 0x83]]).await;
+        });
+
+        let err = register(&mut socket, &mut config).await.unwrap_err();
+        assert_eq!(
+            err.downcast::<zvt::ZVTError>().unwrap(),
+            zvt::ZVTError::Aborted(0x83)
+        );
+        pt_task.await.unwrap();
+        assert_eq!(config.feig_config.password, 123456);
+    }
+
+    #[tokio::test]
+    async fn test_log_off() {
+        let (ecr, mut pt) = tokio::io::duplex(64);
+        let mut socket = TcpStream::with_duplex(get_config(), ecr);
+
+        let pt_task = tokio::spawn(async move {
+            let mut buf = [0; 3];
+            pt.read_exact(&mut buf).await.unwrap();
+            assert_eq!(buf, [0x06, 0x02, 0x00]);
+            pt.write_all(&[0x80, 0x00, 0x00]).await.unwrap();
+        });
+
+        // The PT only acknowledges the log off - we must not wait for more.
+        tokio::time::timeout(Duration::from_secs(1), socket.log_off())
+            .await
+            .unwrap()
+            .unwrap();
+        assert!(socket.inner.is_none());
+        pt_task.await.unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_log_off_without_connection() {
+        let mut socket = TcpStream {
+            config: get_config(),
+            inner: None,
+        };
+
+        // Without a connection there is nothing to log off from.
+        assert!(socket.log_off().await.is_ok());
+        assert!(socket.inner.is_none());
     }
 }